@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    pin::pin,
+};
+
+use async_graphql::futures_util::TryStreamExt;
+use chrono::Utc;
+use poise::{
+    serenity_prelude as serenity,
+    CreateReply,
+};
+
+use crate::{
+    bot::discord::utils::{
+        format_duration,
+        send_error,
+        send_tautulli_error,
+        ErrorSeverity,
+    },
+    services::{
+        tautulli::models::QueryDays,
+        AppServices,
+    },
+};
+
+const DEFAULT_COUNT: u8 = 10;
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum LeaderboardPeriod {
+    #[name = "day"]
+    Day,
+    #[name = "week"]
+    Week,
+    #[name = "month"]
+    Month,
+    #[name = "all"]
+    All,
+}
+
+impl LeaderboardPeriod {
+    fn title(&self) -> &'static str {
+        match self {
+            LeaderboardPeriod::Day => "Today",
+            LeaderboardPeriod::Week => "This Week",
+            LeaderboardPeriod::Month => "This Month",
+            LeaderboardPeriod::All => "All Time",
+        }
+    }
+}
+
+impl From<LeaderboardPeriod> for QueryDays {
+    fn from(value: LeaderboardPeriod) -> Self {
+        match value {
+            LeaderboardPeriod::Day => QueryDays::Day,
+            LeaderboardPeriod::Week => QueryDays::Week,
+            LeaderboardPeriod::Month => QueryDays::Month,
+            LeaderboardPeriod::All => QueryDays::Total,
+        }
+    }
+}
+
+/// Show the members with the most watch time
+#[poise::command(slash_command)]
+pub async fn leaderboard(
+    ctx: poise::Context<'_, AppServices, serenity::Error>,
+    #[description = "Period to rank watch time over (defaults to week)"] period: Option<
+        LeaderboardPeriod,
+    >,
+    #[description = "How many members to show (defaults to 10)"]
+    #[min = 1]
+    #[max = 25]
+    count: Option<u8>,
+) -> Result<(), serenity::Error> {
+    let period = period.unwrap_or(LeaderboardPeriod::Week);
+    let count = count.unwrap_or(DEFAULT_COUNT) as usize;
+
+    // Plex user id -> Discord user id, for members who are happy to be shown.
+    let members: HashMap<String, String> = match ctx
+        .data()
+        .discord_users_service
+        .list_leaderboard_users()
+        .await
+    {
+        Ok(users) => users
+            .into_iter()
+            .filter_map(|(discord_user, plex_user)| {
+                plex_user.map(|plex_user| (plex_user.id, discord_user.id))
+            })
+            .collect(),
+        Err(err) => {
+            send_error(&ctx, err.message, None, ErrorSeverity::Critical).await?;
+            return Ok(());
+        }
+    };
+
+    // Users which aren't linked or have opted out are skipped, so keep fetching Tautulli's top
+    // users until there are enough members.
+    let top_users = ctx.data().tautulli_service.top_users(period.into());
+    let mut top_users = pin!(top_users);
+    let mut lines = vec![];
+    while lines.len() < count {
+        let user = match top_users.try_next().await {
+            Ok(Some(user)) => user,
+            Ok(None) => break,
+            Err(err) => {
+                send_tautulli_error(&ctx, err).await?;
+                return Ok(());
+            }
+        };
+        if let Some(discord_user_id) = members.get(&user.user_id.to_string()) {
+            lines.push(format!(
+                "**{}.** <@{discord_user_id}> - {} ({} plays)",
+                lines.len() + 1,
+                format_duration(user.duration),
+                user.plays
+            ));
+        }
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("🏆 Leaderboard - {}", period.title()))
+        .description(match lines.is_empty() {
+            true => String::from("Nobody has watched anything yet."),
+            false => lines.join("\n"),
+        })
+        .color(0x00A8FC) // Plex blue color
+        .footer(serenity::CreateEmbedFooter::new(
+            "powered by displex • use /privacy to hide yourself",
+        ))
+        .timestamp(Utc::now());
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use chrono::Utc;
+use poise::{
+    serenity_prelude as serenity,
+    CreateReply,
+};
+
+use crate::{
+    bot::discord::utils::link_url,
+    services::{
+        discord_user::resolver::{
+            SummaryDiscordUserResult,
+            UserSummaryBy,
+        },
+        AppServices,
+    },
+};
+
+/// Link your Discord account to your Plex account
+#[poise::command(slash_command, ephemeral)]
+pub async fn link(
+    ctx: poise::Context<'_, AppServices, serenity::Error>,
+) -> Result<(), serenity::Error> {
+    let user_id = ctx.author().id.get().to_string();
+    if let Ok(SummaryDiscordUserResult::Ok(summary)) = ctx
+        .data()
+        .discord_users_service
+        .summary(&UserSummaryBy::Id(user_id.clone()))
+        .await
+    {
+        if let Some(plex_user) = summary.summary.plex_users.first() {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!(
+                        "Your Discord account is already linked to the Plex account **{}**.",
+                        plex_user.username
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    }
+
+    if let poise::Context::Application(app_ctx) = ctx {
+        if let Err(err) = ctx
+            .data()
+            .link_requests_service
+            .create(&user_id, &app_ctx.interaction.token)
+            .await
+        {
+            // The link flow still works, we just won't be able to update this message.
+            tracing::warn!("unable to save link request for {user_id}: {err:?}");
+        }
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Link Your Plex Account")
+        .description("Sign in with Discord and then Plex to link your accounts and confirm you're a subscriber. This message will update once you're done.")
+        .color(0xE5A00D) // Plex amber
+        .field(
+            "Is it safe?",
+            "Yes! The linking process is secure and only provides us with the minimal permissions needed to associate and verify your accounts.",
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+        .timestamp(Utc::now());
+    let button =
+        serenity::CreateButton::new_link(link_url(&ctx.data().config)).label("Link Account");
+
+    ctx.send(
+        CreateReply::default()
+            .embed(embed)
+            .components(vec![serenity::CreateActionRow::Buttons(vec![button])]),
+    )
+    .await?;
+    Ok(())
+}
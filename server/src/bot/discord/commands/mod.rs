@@ -1,9 +1,21 @@
 mod general;
+mod leaderboard;
+mod link;
+mod nowplaying;
+mod privacy;
+mod request;
 mod stats;
 mod subscribers;
+mod unlink;
 
 pub use self::{
     general::*,
+    leaderboard::*,
+    link::*,
+    nowplaying::*,
+    privacy::*,
+    request::*,
     stats::*,
     subscribers::*,
+    unlink::*,
 };
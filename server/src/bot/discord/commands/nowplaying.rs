@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use poise::{
+    serenity_prelude as serenity,
+    CreateReply,
+};
+
+use crate::{
+    bot::discord::utils::{
+        send_error,
+        send_tautulli_error,
+        ErrorSeverity,
+    },
+    server::cookies::Role,
+    services::{
+        tautulli::models::ActivitySession,
+        AppServices,
+    },
+};
+
+/// Discord only allows this many fields in an embed.
+const MAX_SESSIONS: usize = 25;
+
+/// Show what is currently playing on the Plex server
+#[poise::command(slash_command, ephemeral)]
+pub async fn nowplaying(
+    ctx: poise::Context<'_, AppServices, serenity::Error>,
+) -> Result<(), serenity::Error> {
+    let is_moderator = ctx
+        .data()
+        .roles_service
+        .role_for(&ctx.author().id.get().to_string())
+        .await
+        .includes(Role::Moderator);
+
+    let activity = match ctx.data().tautulli_service.get_activity().await {
+        Ok(activity) => activity,
+        Err(err) => {
+            send_tautulli_error(&ctx, err).await?;
+            return Ok(());
+        }
+    };
+
+    // Plex user id -> Discord user, for linked members.
+    let members: HashMap<String, _> =
+        match ctx.data().discord_users_service.list_linked_users().await {
+            Ok(users) => users
+                .into_iter()
+                .filter_map(|(discord_user, plex_user)| {
+                    plex_user.map(|plex_user| (plex_user.id, discord_user))
+                })
+                .collect(),
+            Err(err) => {
+                send_error(&ctx, err.message, None, ErrorSeverity::Critical).await?;
+                return Ok(());
+            }
+        };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!(
+            "▶️ Now Playing ({} streams)",
+            activity.sessions.len()
+        ))
+        .color(0x00A8FC) // Plex blue color
+        .footer(serenity::CreateEmbedFooter::new(
+            "powered by displex • use /privacy to show what you're watching",
+        ))
+        .timestamp(Utc::now());
+    if activity.sessions.is_empty() {
+        embed = embed.description("Nothing is playing right now.");
+    }
+
+    for session in activity.sessions.iter().take(MAX_SESSIONS) {
+        let member = members.get(&session.user_id.to_string());
+        let streamer = match (member, is_moderator) {
+            (Some(member), true) => format!("{} (<@{}>)", session.friendly_name, member.id),
+            (None, true) => session.friendly_name.clone(),
+            (Some(member), false) if member.show_now_playing => format!("<@{}>", member.id),
+            _ => String::from("Someone"),
+        };
+        let mut details = format!(
+            "{streamer}\n{} {}%",
+            state_icon(&session.state),
+            session.progress_percent
+        );
+        if is_moderator {
+            details.push_str(&moderator_details(session));
+        }
+        embed = embed.field(&session.full_title, details, false);
+    }
+    if activity.sessions.len() > MAX_SESSIONS {
+        embed = embed.description(format!(
+            "Showing {MAX_SESSIONS} of {} streams.",
+            activity.sessions.len()
+        ));
+    }
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+fn state_icon(state: &str) -> &'static str {
+    match state {
+        "playing" => "▶️",
+        "paused" => "⏸️",
+        "buffering" => "⏳",
+        _ => "❔",
+    }
+}
+
+fn moderator_details(session: &ActivitySession) -> String {
+    format!(
+        " • {} ({})\n{} • {} {} • {} {:.1} Mbps",
+        session.player,
+        session.product,
+        session.transcode_decision,
+        session.quality_profile,
+        session.stream_video_full_resolution,
+        session.location.to_uppercase(),
+        session.bandwidth as f32 / 1024.0
+    )
+}
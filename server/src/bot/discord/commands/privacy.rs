@@ -0,0 +1,89 @@
+use chrono::Utc;
+use poise::{
+    serenity_prelude as serenity,
+    CreateReply,
+};
+
+use crate::{
+    bot::discord::utils::{
+        send_error,
+        ErrorSeverity,
+    },
+    services::{
+        discord_user::resolver::{
+            GetDiscordUserResult,
+            UpdateDiscordUserResult,
+        },
+        AppServices,
+    },
+};
+
+/// View or change what other members can see about you
+#[poise::command(slash_command, ephemeral)]
+pub async fn privacy(
+    ctx: poise::Context<'_, AppServices, serenity::Error>,
+    #[description = "Show your watch time on /leaderboard"] leaderboard: Option<bool>,
+    #[description = "Show what you're watching on /nowplaying"] now_playing: Option<bool>,
+) -> Result<(), serenity::Error> {
+    let user_id = ctx.author().id.get().to_string();
+    let discord_users_svc = &ctx.data().discord_users_service;
+
+    let result = match (leaderboard, now_playing) {
+        (None, None) => discord_users_svc
+            .get(&user_id)
+            .await
+            .map(|result| match result {
+                GetDiscordUserResult::Ok(user) => Some(user),
+                GetDiscordUserResult::Err(_) => None,
+            }),
+        _ => discord_users_svc
+            .update(&user_id, None, leaderboard, now_playing)
+            .await
+            .map(|result| match result {
+                UpdateDiscordUserResult::Ok(user) => Some(user),
+                UpdateDiscordUserResult::Err(_) => None,
+            }),
+    };
+    let user = match result {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            send_error(
+                &ctx,
+                format!("no data stored for {user_id}"),
+                Some("Your Discord account isn't linked yet, use `/link` to get started."),
+                ErrorSeverity::Info,
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(err) => {
+            send_error(&ctx, err.message, None, ErrorSeverity::Critical).await?;
+            return Ok(());
+        }
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Privacy Settings")
+        .color(0x3498DB) // Blue
+        .field(
+            "Leaderboard",
+            match user.show_on_leaderboard {
+                true => "Your watch time is shown on `/leaderboard`.",
+                false => "You are hidden from `/leaderboard`.",
+            },
+            false,
+        )
+        .field(
+            "Now Playing",
+            match user.show_now_playing {
+                true => "Other members can see what you're watching on `/nowplaying`.",
+                false => "You are shown anonymously on `/nowplaying`.",
+            },
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+        .timestamp(Utc::now());
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
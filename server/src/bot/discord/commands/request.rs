@@ -0,0 +1,299 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::Utc;
+use poise::{
+    serenity_prelude as serenity,
+    CreateReply,
+};
+
+use crate::{
+    bot::discord::utils::{
+        send_error,
+        ErrorSeverity,
+    },
+    services::{
+        discord_user::resolver::{
+            SummaryDiscordUserResult,
+            UserSummaryBy,
+        },
+        overseerr::models::{
+            Media,
+            MediaStatus,
+            MediaType,
+        },
+        AppServices,
+    },
+};
+
+/// Autocomplete choices are submitted as `tmdb:<id>` so the exact result can be requested.
+const TMDB_PREFIX: &str = "tmdb:";
+/// How long the confirmation buttons stay active for.
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+/// Discord limits autocomplete choices to 25, and their names to 100 characters.
+const MAX_CHOICES: usize = 25;
+const MAX_CHOICE_NAME_LENGTH: usize = 100;
+
+type Context<'a> = poise::Context<'a, AppServices, serenity::Error>;
+
+/// Request a movie or TV show on Overseerr
+#[poise::command(slash_command, subcommands("movie", "tv"), subcommand_required)]
+pub async fn request(_ctx: Context<'_>) -> Result<(), serenity::Error> {
+    Ok(())
+}
+
+/// Request a movie on Overseerr
+#[poise::command(slash_command, ephemeral)]
+pub async fn movie(
+    ctx: Context<'_>,
+    #[description = "Movie to request"]
+    #[autocomplete = "autocomplete_movie"]
+    query: String,
+) -> Result<(), serenity::Error> {
+    request_media(ctx, MediaType::Movie, &query).await
+}
+
+/// Request every season of a TV show on Overseerr
+#[poise::command(slash_command, ephemeral)]
+pub async fn tv(
+    ctx: Context<'_>,
+    #[description = "TV show to request"]
+    #[autocomplete = "autocomplete_tv"]
+    query: String,
+) -> Result<(), serenity::Error> {
+    request_media(ctx, MediaType::Tv, &query).await
+}
+
+async fn autocomplete_movie(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+    autocomplete(ctx, MediaType::Movie, partial).await
+}
+
+async fn autocomplete_tv(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+    autocomplete(ctx, MediaType::Tv, partial).await
+}
+
+async fn autocomplete(
+    ctx: Context<'_>,
+    media_type: MediaType,
+    partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+    if partial.trim().is_empty() {
+        return vec![];
+    }
+    match ctx
+        .data()
+        .overseerr_service
+        .search(partial, media_type)
+        .await
+    {
+        Ok(results) => results
+            .into_iter()
+            .take(MAX_CHOICES)
+            .map(|media| {
+                serenity::AutocompleteChoice::new(
+                    media
+                        .display_title()
+                        .chars()
+                        .take(MAX_CHOICE_NAME_LENGTH)
+                        .collect::<String>(),
+                    format!("{TMDB_PREFIX}{}", media.id),
+                )
+            })
+            .collect(),
+        Err(err) => {
+            tracing::warn!("unable to search Overseerr for {partial:?}: {err:?}");
+            vec![]
+        }
+    }
+}
+
+/// Find the media the user picked, either from autocomplete or by taking the best search result.
+async fn find_media(
+    ctx: &Context<'_>,
+    media_type: MediaType,
+    query: &str,
+) -> anyhow::Result<Option<Media>> {
+    let overseerr_svc = &ctx.data().overseerr_service;
+    if let Some(id) = query
+        .strip_prefix(TMDB_PREFIX)
+        .and_then(|id| id.parse().ok())
+    {
+        return Ok(Some(overseerr_svc.get_media(media_type, id).await?));
+    }
+    Ok(overseerr_svc
+        .search(query, media_type)
+        .await?
+        .into_iter()
+        .next())
+}
+
+async fn request_media(
+    ctx: Context<'_>,
+    media_type: MediaType,
+    query: &str,
+) -> Result<(), serenity::Error> {
+    let plex_user = match ctx
+        .data()
+        .discord_users_service
+        .summary(&UserSummaryBy::Id(ctx.author().id.get().to_string()))
+        .await
+    {
+        Ok(SummaryDiscordUserResult::Ok(summary)) => summary.summary.plex_users.into_iter().next(),
+        _ => None,
+    };
+    let Some(plex_user) = plex_user else {
+        send_error(
+            &ctx,
+            anyhow!("User has no linked Plex account"),
+            Some("Your Discord account is not linked to a Plex account, use `/link` first."),
+            ErrorSeverity::Warning,
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let overseerr_user = match ctx
+        .data()
+        .overseerr_service
+        .get_user_by_plex_id(&plex_user.id)
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            send_error(
+                &ctx,
+                format!("no Overseerr user for {}", plex_user.id),
+                Some("You don't have an Overseerr account yet, sign in to Overseerr with Plex first."),
+                ErrorSeverity::Warning,
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(err) => {
+            send_error(
+                &ctx,
+                err,
+                Some("Unable to reach Overseerr."),
+                ErrorSeverity::Critical,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let media = match find_media(&ctx, media_type, query).await {
+        Ok(Some(media)) => media,
+        Ok(None) => {
+            send_error(
+                &ctx,
+                format!("no {media_type} found for {query:?}"),
+                Some(&format!("Nothing matched **{query}**.")),
+                ErrorSeverity::Info,
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(err) => {
+            send_error(
+                &ctx,
+                err,
+                Some("Unable to search Overseerr."),
+                ErrorSeverity::Critical,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let status = media.media_info.as_ref().map(|info| info.status);
+
+    let ctx_id = ctx.id();
+    let confirm_id = format!("{ctx_id}-confirm");
+    let cancel_id = format!("{ctx_id}-cancel");
+    let mut embed = serenity::CreateEmbed::new()
+        .title(media.display_title())
+        .description(media.overview.clone().unwrap_or_default())
+        .color(0xE5A00D) // Plex amber
+        .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+        .timestamp(Utc::now());
+    if let Some(poster_url) = media.poster_url() {
+        embed = embed.thumbnail(poster_url);
+    }
+    if let Some(status) = status {
+        embed = embed.field("Status", status.to_string(), true);
+    }
+    let buttons = vec![
+        serenity::CreateButton::new(&confirm_id)
+            .style(serenity::ButtonStyle::Success)
+            .label("Request")
+            .disabled(status == Some(MediaStatus::Available)),
+        serenity::CreateButton::new(&cancel_id)
+            .style(serenity::ButtonStyle::Secondary)
+            .label("Cancel"),
+    ];
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .embed(embed)
+                .components(vec![serenity::CreateActionRow::Buttons(buttons)]),
+        )
+        .await?;
+
+    let prefix = ctx_id.to_string();
+    let interaction = serenity::ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .timeout(CONFIRMATION_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id.starts_with(&prefix))
+        .await;
+
+    let embed = match interaction {
+        Some(mci) if mci.data.custom_id == confirm_id => {
+            mci.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            match ctx
+                .data()
+                .overseerr_service
+                .create_request(overseerr_user.id, media_type, media.id)
+                .await
+            {
+                Ok(_) => serenity::CreateEmbed::new()
+                    .title("✅ Requested")
+                    .description(format!("**{}** has been requested.", media.display_title()))
+                    .color(0x2ECC71), // Green
+                Err(err) => {
+                    tracing::warn!(
+                        "{} failed to request {media_type} {}: {err:?}",
+                        overseerr_user.id,
+                        media.id
+                    );
+                    serenity::CreateEmbed::new()
+                        .title("❌ Request Failed")
+                        .description(format!(
+                            "Unable to request **{}**, you may have reached your request \
+                             limit. Please try again later.",
+                            media.display_title()
+                        ))
+                        .color(0xE74C3C) // Red
+                }
+            }
+        }
+        Some(mci) => {
+            mci.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            serenity::CreateEmbed::new()
+                .title("Request Cancelled")
+                .description("Nothing has been requested.")
+                .color(0x3498DB) // Blue
+        }
+        None => serenity::CreateEmbed::new()
+            .title("Request Timed Out")
+            .description("Nothing has been requested.")
+            .color(0x3498DB), // Blue
+    }
+    .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+    .timestamp(Utc::now());
+
+    reply
+        .edit(ctx, CreateReply::default().embed(embed).components(vec![]))
+        .await?;
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use crate::{
     bot::discord::utils::{
+        format_duration,
+        link_url,
         send_error,
+        send_tautulli_error,
         ErrorSeverity,
     },
     services::{
@@ -53,13 +56,7 @@ pub async fn stats(
         {
             Ok(stats) => stats,
             Err(err) => {
-                send_error(
-                    &ctx,
-                    err,
-                    Some("An error has occurred"),
-                    ErrorSeverity::Critical,
-                )
-                .await?;
+                send_tautulli_error(&ctx, err).await?;
                 return Ok(());
             }
         };
@@ -72,13 +69,7 @@ pub async fn stats(
         {
             Ok(stats) => stats,
             Err(err) => {
-                send_error(
-                    &ctx,
-                    err,
-                    Some("An error has occurred"),
-                    ErrorSeverity::Critical,
-                )
-                .await?;
+                send_tautulli_error(&ctx, err).await?;
                 return Ok(());
             }
         };
@@ -91,13 +82,7 @@ pub async fn stats(
         {
             Ok(stats) => stats,
             Err(err) => {
-                send_error(
-                    &ctx,
-                    err,
-                    Some("An error has occurred"),
-                    ErrorSeverity::Critical,
-                )
-                .await?;
+                send_tautulli_error(&ctx, err).await?;
                 return Ok(());
             }
         };
@@ -110,29 +95,11 @@ pub async fn stats(
         {
             Ok(stats) => stats,
             Err(err) => {
-                send_error(
-                    &ctx,
-                    err,
-                    Some("An error has occurred"),
-                    ErrorSeverity::Critical,
-                )
-                .await?;
+                send_tautulli_error(&ctx, err).await?;
                 return Ok(());
             }
         };
 
-        // Format time durations nicely
-        fn format_duration(seconds: i32) -> String {
-            let hours = seconds / 3600;
-            let minutes = (seconds % 3600) / 60;
-
-            if hours > 0 {
-                format!("{}h {}m", hours, minutes)
-            } else {
-                format!("{}m", minutes)
-            }
-        }
-
         // Create an embed with the data
         let embed = serenity::CreateEmbed::new()
             .title(format!("Watch Stats for {}", user.name))
@@ -148,7 +115,7 @@ pub async fn stats(
                     today_stats.first().map_or(0, |s| s.total_plays),
                     today_stats
                         .first()
-                        .map_or(String::from("0m"), |s| format_duration(s.total_time))
+                        .map_or(String::from("0m"), |s| format_duration(s.total_time.into()))
                 ),
                 true,
             )
@@ -159,7 +126,7 @@ pub async fn stats(
                     week_stats.first().map_or(0, |s| s.total_plays),
                     week_stats
                         .first()
-                        .map_or(String::from("0m"), |s| format_duration(s.total_time))
+                        .map_or(String::from("0m"), |s| format_duration(s.total_time.into()))
                 ),
                 true,
             )
@@ -170,7 +137,7 @@ pub async fn stats(
                     month_stats.first().map_or(0, |s| s.total_plays),
                     month_stats
                         .first()
-                        .map_or(String::from("0m"), |s| format_duration(s.total_time))
+                        .map_or(String::from("0m"), |s| format_duration(s.total_time.into()))
                 ),
                 true,
             )
@@ -181,7 +148,7 @@ pub async fn stats(
                     all_time_stats.first().map_or(0, |s| s.total_plays),
                     all_time_stats
                         .first()
-                        .map_or(String::from("0m"), |s| format_duration(s.total_time))
+                        .map_or(String::from("0m"), |s| format_duration(s.total_time.into()))
                 ),
                 true,
             )
@@ -195,11 +162,7 @@ pub async fn stats(
         )
         .await?;
     } else {
-        let auth_url = format!(
-            "https://{}/auth/discord?next=/auth/plex?next=discord://-/channels/{}/@home",
-            ctx.data().config.http.hostname,
-            ctx.data().config.discord.server_id
-        );
+        let auth_url = link_url(&ctx.data().config);
 
         let embed = serenity::CreateEmbed::new()
         .title("Account Linking & Verification Required")
@@ -207,7 +170,7 @@ pub async fn stats(
         .color(0xE5A00D) // Warning color (amber)
         .field(
             "How to Link Your Account",
-            format!("Click [here]({}) to link your accounts\n\nOr follow these steps manually:\n1. Go to Server Settings\n2. Click on Linked Roles\n3. Follow the steps to connect your Plex account", auth_url),
+            format!("Click [here]({}) or use `/link` to link your accounts\n\nOr follow these steps manually:\n1. Go to Server Settings\n2. Click on Linked Roles\n3. Follow the steps to connect your Plex account", auth_url),
             false,
         )
         .field(
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use poise::{
+    serenity_prelude as serenity,
+    CreateReply,
+};
+
+use crate::{
+    bot::discord::utils::{
+        send_error,
+        ErrorSeverity,
+    },
+    services::{
+        discord_user::resolver::{
+            DeleteDiscordUserErrorVariant,
+            DeleteDiscordUserResult,
+            SummaryDiscordUserResult,
+            UserSummaryBy,
+        },
+        AppServices,
+    },
+};
+
+/// How long the confirmation buttons stay active for.
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Unlink your Plex account and delete all of your data
+#[poise::command(slash_command, ephemeral)]
+pub async fn unlink(
+    ctx: poise::Context<'_, AppServices, serenity::Error>,
+) -> Result<(), serenity::Error> {
+    let user_id = ctx.author().id.get().to_string();
+    let plex_username = match ctx
+        .data()
+        .discord_users_service
+        .summary(&UserSummaryBy::Id(user_id.clone()))
+        .await
+    {
+        Ok(SummaryDiscordUserResult::Ok(summary)) => summary
+            .summary
+            .plex_users
+            .first()
+            .map(|plex_user| plex_user.username.clone()),
+        _ => {
+            send_error(
+                &ctx,
+                format!("no data stored for {user_id}"),
+                Some("We don't have any data stored for your Discord account."),
+                ErrorSeverity::Info,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let ctx_id = ctx.id();
+    let confirm_id = format!("{ctx_id}-confirm");
+    let cancel_id = format!("{ctx_id}-cancel");
+    let embed = serenity::CreateEmbed::new()
+        .title("Unlink Your Account?")
+        .description(match &plex_username {
+            Some(plex_username) => format!("This will unlink the Plex account **{plex_username}** from your Discord account."),
+            None => String::from("This will remove your Discord account from displex."),
+        })
+        .color(0xE5A00D) // Warning color (amber)
+        .field(
+            "What gets removed?",
+            "Your Discord authorization is revoked, your linked role is cleared and every account and token we have stored for you is deleted. You can run `/link` again at any time.",
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+        .timestamp(Utc::now());
+    let buttons = vec![
+        serenity::CreateButton::new(&confirm_id)
+            .style(serenity::ButtonStyle::Danger)
+            .label("Unlink"),
+        serenity::CreateButton::new(&cancel_id)
+            .style(serenity::ButtonStyle::Secondary)
+            .label("Cancel"),
+    ];
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .embed(embed)
+                .components(vec![serenity::CreateActionRow::Buttons(buttons)]),
+        )
+        .await?;
+
+    let prefix = ctx_id.to_string();
+    let interaction = serenity::ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .timeout(CONFIRMATION_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id.starts_with(&prefix))
+        .await;
+
+    let embed = match interaction {
+        Some(mci) if mci.data.custom_id == confirm_id => {
+            // Unlinking talks to Discord and the database, so acknowledge the press first.
+            mci.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            match ctx.data().discord_users_service.unlink(&user_id).await {
+                Ok(DeleteDiscordUserResult::Ok(_)) => serenity::CreateEmbed::new()
+                    .title("✅ Account Unlinked")
+                    .description("Your accounts have been unlinked and your data has been deleted.")
+                    .color(0x2ECC71), // Green
+                Ok(DeleteDiscordUserResult::Err(err))
+                    if err.error == DeleteDiscordUserErrorVariant::UserDoesNotExist =>
+                {
+                    serenity::CreateEmbed::new()
+                        .title("ℹ️ Nothing To Unlink")
+                        .description("We don't have any data stored for your Discord account.")
+                        .color(0x3498DB) // Blue
+                }
+                result => {
+                    tracing::error!("{user_id} failed to unlink: {:?}", result);
+                    serenity::CreateEmbed::new()
+                        .title("❌ Unlinking Failed")
+                        .description("We were unable to delete your data. Please try again later.")
+                        .color(0xE74C3C) // Red
+                }
+            }
+        }
+        Some(mci) => {
+            mci.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            serenity::CreateEmbed::new()
+                .title("Unlink Cancelled")
+                .description("Nothing has been removed.")
+                .color(0x3498DB) // Blue
+        }
+        None => serenity::CreateEmbed::new()
+            .title("Unlink Timed Out")
+            .description("Nothing has been removed. Run `/unlink` again if you still want to unlink your account.")
+            .color(0x3498DB), // Blue
+    }
+    .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+    .timestamp(Utc::now());
+
+    reply
+        .edit(ctx, CreateReply::default().embed(embed).components(vec![]))
+        .await?;
+    Ok(())
+}
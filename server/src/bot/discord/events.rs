@@ -0,0 +1,46 @@
+use tokio::sync::broadcast::{
+    error::RecvError,
+    Receiver,
+};
+
+use crate::{
+    bot::discord::notifications,
+    services::{
+        events::Event,
+        AppServices,
+    },
+};
+
+/// Post notifications for events from the event bus until a shutdown signal is received.
+pub async fn run(mut kill: Receiver<()>, services: AppServices) {
+    let Some(channel_id) = services.config.tautulli.webhook.channel_id else {
+        tracing::debug!("no notification channel configured, not listening for events");
+        return;
+    };
+    let mut events = services.event_bus.subscribe();
+    loop {
+        let event = tokio::select! {
+            _ = kill.recv() => break,
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("bot missed {skipped} events");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        let message = match &event {
+            Event::Tautulli(event) => notifications::tautulli_event(event),
+        };
+        if let Some(message) = message {
+            if let Err(err) = services
+                .discord_service
+                .send_message(channel_id, message)
+                .await
+            {
+                tracing::warn!("unable to post notification for {event:?}: {err:?}");
+            }
+        }
+    }
+}
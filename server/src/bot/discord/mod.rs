@@ -2,12 +2,22 @@ use std::collections::HashSet;
 
 use anyhow::Result;
 
+use async_trait::async_trait;
 use serenity::{
+    all::{
+        FullEvent,
+        Interaction,
+    },
     client::ClientBuilder,
+    framework::Framework,
     http::HttpBuilder,
     prelude::*,
 };
 use tokio::sync::broadcast::Receiver;
+use tracing::{
+    Instrument,
+    Span,
+};
 
 use crate::{
     config::AppConfig,
@@ -15,8 +25,57 @@ use crate::{
 };
 
 mod commands;
+mod events;
+pub mod notifications;
 mod utils;
 
+const PREFIX: &str = "~";
+
+/// Runs each command in its own trace, so it can be followed through the services it calls.
+struct TracedFramework(poise::Framework<AppServices, serenity::Error>);
+
+#[async_trait]
+impl Framework for TracedFramework {
+    async fn init(&mut self, client: &serenity::Client) {
+        self.0.init(client).await
+    }
+
+    async fn dispatch(&self, ctx: Context, event: FullEvent) {
+        let span = match &event {
+            FullEvent::InteractionCreate {
+                interaction: Interaction::Command(interaction),
+            } => tracing::info_span!(
+                parent: None,
+                "command",
+                command = interaction.data.name,
+                user_id = %interaction.user.id,
+            ),
+            FullEvent::InteractionCreate {
+                interaction: Interaction::Autocomplete(interaction),
+            } => tracing::info_span!(
+                parent: None,
+                "autocomplete",
+                command = interaction.data.name,
+                user_id = %interaction.user.id,
+            ),
+            FullEvent::Message { new_message } if new_message.content.starts_with(PREFIX) => {
+                tracing::info_span!(
+                    parent: None,
+                    "command",
+                    command = new_message
+                        .content
+                        .trim_start_matches(PREFIX)
+                        .split_whitespace()
+                        .next(),
+                    user_id = %new_message.author.id,
+                )
+            }
+            _ => Span::none(),
+        };
+        self.0.dispatch(ctx, event).instrument(span).await
+    }
+}
+
 pub async fn init(config: AppConfig, services: &AppServices) -> Result<serenity::Client> {
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
@@ -41,12 +100,18 @@ pub async fn init(config: AppConfig, services: &AppServices) -> Result<serenity:
 
     let options = poise::FrameworkOptions {
         commands: vec![
+            commands::leaderboard(),
+            commands::link(),
+            commands::nowplaying(),
             commands::ping(),
+            commands::privacy(),
+            commands::request(),
             commands::subscriber_tokens(),
             commands::stats(),
+            commands::unlink(),
         ],
         prefix_options: poise::PrefixFrameworkOptions {
-            prefix: Some("~".into()),
+            prefix: Some(PREFIX.into()),
             ..Default::default()
         },
         // Enforce command checks even for owners (enforced by default)
@@ -73,14 +138,20 @@ pub async fn init(config: AppConfig, services: &AppServices) -> Result<serenity:
         .build();
 
     let client = ClientBuilder::new_with_http(http_client, intents)
-        .framework(framework)
+        .framework(TracedFramework(framework))
         .await?;
 
     Ok(client)
 }
 
-pub async fn run(mut kill: Receiver<()>, mut serenity_client: serenity::Client) {
+pub async fn run(
+    mut kill: Receiver<()>,
+    mut serenity_client: serenity::Client,
+    services: &AppServices,
+) {
+    tokio::spawn(events::run(kill.resubscribe(), services.clone()));
     let manager = serenity_client.shard_manager.clone();
+    services.discord_service.set_shard_manager(manager.clone());
     tokio::spawn(async move {
         tokio::select! {
             _ = kill.recv() => tracing::info!("shutting down bot..."),
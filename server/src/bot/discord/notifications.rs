@@ -0,0 +1,123 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+
+use crate::services::{
+    events::{
+        TautulliEvent,
+        TautulliEventKind,
+    },
+    overseerr::models::{
+        WebhookNotificationType,
+        WebhookPayload,
+    },
+};
+
+/// Build the reply shown once a `/link` flow has finished.
+///
+/// # Arguments
+///
+/// * `result` - The linked Plex username and whether they are a subscriber, or `None` if linking
+///   failed
+pub fn link_completed(result: Option<(&str, bool)>) -> serenity::EditInteractionResponse {
+    let embed = match result {
+        Some((plex_username, is_subscribed)) => serenity::CreateEmbed::new()
+            .title("✅ Accounts Linked")
+            .description(format!(
+                "Your Discord account is now linked to the Plex account **{plex_username}**."
+            ))
+            .color(0x2ECC71) // Green
+            .field(
+                "Subscriber",
+                match is_subscribed {
+                    true => "Yes, you have access to the Plex server.",
+                    false => "No, this Plex account does not have access to the Plex server.",
+                },
+                false,
+            ),
+        None => serenity::CreateEmbed::new()
+            .title("❌ Linking Failed")
+            .description("We were unable to link your accounts. Please run `/link` to try again.")
+            .color(0xE74C3C), // Red
+    }
+    .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+    .timestamp(Utc::now());
+
+    serenity::EditInteractionResponse::new()
+        .embed(embed)
+        .components(vec![])
+}
+
+/// Build the message sent when an Overseerr request is approved, declined or becomes available.
+///
+/// # Arguments
+///
+/// * `payload` - The webhook payload sent by Overseerr
+/// * `mention` - The Discord user to mention, used when posting to a channel rather than a DM
+///
+/// # Returns
+///
+/// * `None` if the notification type isn't one users are told about
+pub fn request_status(
+    payload: &WebhookPayload,
+    mention: Option<u64>,
+) -> Option<serenity::CreateMessage> {
+    let (title, color) = match payload.notification_type {
+        WebhookNotificationType::MediaApproved | WebhookNotificationType::MediaAutoApproved => {
+            ("✅ Request Approved", 0x2ECC71) // Green
+        }
+        WebhookNotificationType::MediaDeclined => ("❌ Request Declined", 0xE74C3C), // Red
+        WebhookNotificationType::MediaAvailable => ("🎉 Now Available", 0x00A8FC),   // Plex blue
+        _ => return None,
+    };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(title)
+        .description(match &payload.message {
+            Some(message) if !message.is_empty() => {
+                format!("**{}**\n{message}", payload.subject)
+            }
+            _ => format!("**{}**", payload.subject),
+        })
+        .color(color)
+        .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+        .timestamp(Utc::now());
+    if let Some(image) = payload.image.as_ref().filter(|image| !image.is_empty()) {
+        embed = embed.thumbnail(image);
+    }
+
+    let mut message = serenity::CreateMessage::new().embed(embed);
+    if let Some(user_id) = mention {
+        message = message.content(format!("<@{user_id}>"));
+    }
+    Some(message)
+}
+
+/// Build the message posted to the notification channel for a Tautulli event.
+///
+/// # Returns
+///
+/// * `None` for events which aren't posted, plays and stops are kept private
+pub fn tautulli_event(event: &TautulliEvent) -> Option<serenity::CreateMessage> {
+    let embed = match event.kind {
+        TautulliEventKind::RecentlyAdded => serenity::CreateEmbed::new()
+            .title("🆕 Recently Added")
+            .description(format!(
+                "**{}** is now available.",
+                event.title.as_deref().unwrap_or("Something new")
+            ))
+            .color(0x00A8FC), // Plex blue
+        TautulliEventKind::ServerDown => serenity::CreateEmbed::new()
+            .title("🔴 Plex Server Down")
+            .description("The Plex server is unreachable, we're looking into it.")
+            .color(0xE74C3C), // Red
+        TautulliEventKind::ServerUp => serenity::CreateEmbed::new()
+            .title("🟢 Plex Server Back Up")
+            .description("The Plex server is reachable again.")
+            .color(0x2ECC71), // Green
+        TautulliEventKind::Play | TautulliEventKind::Stop => return None,
+    }
+    .footer(serenity::CreateEmbedFooter::new("powered by displex"))
+    .timestamp(event.received_at);
+
+    Some(serenity::CreateMessage::new().embed(embed))
+}
@@ -5,7 +5,34 @@ use tracing::{
     warn,
 };
 
-use crate::services::AppServices;
+use crate::{
+    config::AppConfig,
+    services::{
+        tautulli::TautulliError,
+        AppServices,
+    },
+};
+
+/// URL which takes a user through the Discord and Plex OAuth flows, returning them to the Discord
+/// server once they are done.
+pub fn link_url(config: &AppConfig) -> String {
+    format!(
+        "https://{}/auth/discord?next=/auth/plex?next=discord://-/channels/{}/@home",
+        config.http.hostname, config.discord.server_id
+    )
+}
+
+/// Format a number of seconds as hours and minutes, e.g. `3h 25m`.
+pub fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
 
 /// Send a formatted error message to Discord and log the error
 ///
@@ -85,6 +112,28 @@ pub async fn send_error<E: std::fmt::Display>(
     Ok(())
 }
 
+/// Send an error message which says whether Tautulli is offline or rejected the request.
+pub async fn send_tautulli_error(
+    ctx: &poise::Context<'_, AppServices, serenity::Error>,
+    error: TautulliError,
+) -> Result<(), serenity::Error> {
+    let (public_message, severity) = match &error {
+        _ if error.is_unavailable() => (
+            String::from("Tautulli is offline, please try again later."),
+            ErrorSeverity::Warning,
+        ),
+        TautulliError::Api(message) => (
+            format!("Tautulli couldn't complete the request: {message}"),
+            ErrorSeverity::Warning,
+        ),
+        TautulliError::Transport(_) | TautulliError::Status(_) | TautulliError::Schema(_) => (
+            String::from("Tautulli returned an unexpected response."),
+            ErrorSeverity::Critical,
+        ),
+    };
+    send_error(ctx, error, Some(&public_message), severity).await
+}
+
 /// The severity level of an error, affects color and logging
 pub enum ErrorSeverity {
     /// Critical errors (red) - unexpected failures that need attention
@@ -8,6 +8,8 @@ use serde::{
 };
 use tokio::sync::broadcast::Receiver;
 
+use crate::services::AppServices;
+
 pub mod discord;
 
 #[derive(
@@ -33,14 +35,24 @@ pub enum DiscordBot {
 
 #[async_trait]
 pub trait DisplexBot {
-    async fn run(&self, rx: Receiver<()>, serenity_client: serenity::Client) -> Result<()>;
+    async fn run(
+        &self,
+        rx: Receiver<()>,
+        serenity_client: serenity::Client,
+        services: &AppServices,
+    ) -> Result<()>;
 }
 
 #[async_trait]
 impl DisplexBot for DiscordBot {
-    async fn run(&self, rx: Receiver<()>, serenity_client: serenity::Client) -> Result<()> {
+    async fn run(
+        &self,
+        rx: Receiver<()>,
+        serenity_client: serenity::Client,
+        services: &AppServices,
+    ) -> Result<()> {
         match self {
-            DiscordBot::Serenity => discord::run(rx, serenity_client).await,
+            DiscordBot::Serenity => discord::run(rx, serenity_client, services).await,
             DiscordBot::Disabled => tracing::info!("bot disabled"),
         }
         Ok(())
@@ -1,5 +1,8 @@
 use std::{
-    collections::HashMap,
+    collections::{
+        HashMap,
+        HashSet,
+    },
     fmt,
     path::PathBuf,
     time::Duration,
@@ -45,15 +48,20 @@ pub struct AppConfig {
     pub debug: DebugConfig,
     pub discord: DiscordConfig,
     pub discord_bot: DiscordBotConfig,
+    pub health: HealthConfig,
     pub http: HttpConfig,
     pub http_client: HttpClientConfig,
+    pub metrics: MetricsConfig,
     pub plex: PlexConfig,
     pub overseerr: OverseerrConfig,
     pub session: SessionConfig,
     pub tautulli: TautulliConfig,
+    pub tracing: TracingConfig,
     pub web: WebConfig,
     pub requests_config: RequestsUpgradeConfig,
+    pub role_sync: RoleSyncConfig,
     pub token_maintenance: TokenMaintenanceConfig,
+    pub scheduler: SchedulerConfig,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -95,6 +103,60 @@ impl Default for DatabaseConfig {
     }
 }
 
+/// A dependency checked by `/readyz`.
+#[derive(Deserialize, Debug, Copy, Clone, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Dependency {
+    Database,
+    Tautulli,
+    Overseerr,
+    Discord,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct HealthConfig {
+    /// Dependencies which fail `/readyz` when they are down, the others are only reported.
+    pub required: Vec<Dependency>,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            required: vec![Dependency::Database, Dependency::Tautulli],
+        }
+    }
+}
+
+/// Serves Prometheus metrics at `/metrics`.
+#[derive(Derivative, Deserialize, Clone, Serialize, Default)]
+#[derivative(Debug)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Required as `Authorization: Bearer <bearer_token>` to scrape the metrics, anyone can
+    /// scrape them when empty.
+    #[derivative(Debug(format_with = "obfuscated_formatter"))]
+    pub bearer_token: String,
+}
+
+/// Exports traces to an OpenTelemetry collector over OTLP/HTTP.
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct TracingConfig {
+    pub enabled: bool,
+    /// The collector's base URL, `/v1/traces` is appended to it.
+    pub endpoint: String,
+    pub service_name: String,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::from("http://localhost:4318"),
+            service_name: String::from(PROJECT_NAME),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, Serialize, Default)]
 pub struct DebugConfig {
     pub accept_invalid_certs: bool,
@@ -116,6 +178,9 @@ pub struct HttpConfig {
     pub hostname: String,
     pub host: String,
     pub port: u16,
+    /// Take the client's address from `X-Forwarded-For`. Only enable this behind a reverse
+    /// proxy which sets the header, otherwise clients can claim any address.
+    pub trust_forwarded_for: bool,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -138,21 +203,35 @@ impl Default for HttpClientConfig {
     }
 }
 
-#[derive(Deserialize, Debug, Clone, Serialize)]
+#[derive(Derivative, Deserialize, Clone, Serialize)]
+#[derivative(Debug)]
 pub struct ApiConfig {
     pub enabled: bool,
+    /// Grants admin access to the GraphQL API through `Authorization: Bearer <api_key>`,
+    /// disabled when empty.
+    #[derivative(Debug(format_with = "obfuscated_formatter"))]
     pub api_key: String,
     pub cors_allowed_origins: Vec<String>,
     pub admin_discord_ids: Vec<String>,
+    /// Names or ids of the roles in `discord.server_id` whose members are admins.
+    pub admin_roles: Vec<String>,
+    /// Names or ids of the roles in `discord.server_id` whose members are moderators.
+    pub moderator_roles: Vec<String>,
+    /// How long a user's guild roles are cached for before being fetched again.
+    #[serde(with = "humantime_serde")]
+    pub role_cache_ttl: Duration,
 }
 
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
             enabled: false,
-            api_key: "notasecret".into(),
+            api_key: String::new(),
             cors_allowed_origins: vec![],
             admin_discord_ids: vec![],
+            admin_roles: vec![],
+            moderator_roles: vec![],
+            role_cache_ttl: Duration::from_secs(60 * 5),
         }
     }
 }
@@ -164,6 +243,7 @@ impl Default for HttpConfig {
             port: 8080,
             hostname: "localhost".into(),
             type_: Default::default(),
+            trust_forwarded_for: false,
         }
     }
 }
@@ -195,6 +275,7 @@ pub struct OverseerrConfig {
     pub url: String,
     #[derivative(Debug(format_with = "obfuscated_formatter"))]
     pub api_key: String,
+    pub webhook: OverseerrWebhookConfig,
 }
 
 impl Default for OverseerrConfig {
@@ -202,6 +283,30 @@ impl Default for OverseerrConfig {
         Self {
             url: "http://localhost:5055".into(),
             api_key: Default::default(),
+            webhook: Default::default(),
+        }
+    }
+}
+
+/// Receives request status updates from Overseerr's webhook agent at `/hooks/overseerr`.
+#[derive(Derivative, Deserialize, Clone, Serialize)]
+#[derivative(Debug)]
+pub struct OverseerrWebhookConfig {
+    /// Must match the webhook agent's Authorization Header, the webhook is disabled when empty.
+    #[derivative(Debug(format_with = "obfuscated_formatter"))]
+    pub secret: String,
+    /// DM the Discord user who made the request.
+    pub direct_message: bool,
+    /// Also post every update to this channel.
+    pub channel_id: Option<u64>,
+}
+
+impl Default for OverseerrWebhookConfig {
+    fn default() -> Self {
+        Self {
+            secret: Default::default(),
+            direct_message: true,
+            channel_id: None,
         }
     }
 }
@@ -211,12 +316,17 @@ impl Default for OverseerrConfig {
 pub struct SessionConfig {
     #[derivative(Debug(format_with = "obfuscated_formatter"))]
     pub secret_key: String,
+    /// How long a user stays signed in for.
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
 }
 
 impl Default for SessionConfig {
     fn default() -> Self {
         Self {
             secret_key: "youshouldnotusethisinproductionandchangeme".into(),
+            // 6 months
+            ttl: Duration::from_secs(60 * 60 * 24 * 30 * 6),
         }
     }
 }
@@ -227,6 +337,8 @@ pub struct TautulliConfig {
     pub url: String,
     #[derivative(Debug(format_with = "obfuscated_formatter"))]
     pub api_key: String,
+    pub cache: TautulliCacheConfig,
+    pub webhook: TautulliWebhookConfig,
 }
 
 impl Default for TautulliConfig {
@@ -234,10 +346,47 @@ impl Default for TautulliConfig {
         Self {
             url: "http://localhost:8181".into(),
             api_key: Default::default(),
+            cache: Default::default(),
+            webhook: Default::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct TautulliCacheConfig {
+    /// How long responses are cached for, caching is disabled when zero.
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+    /// TTLs for individual API commands, e.g. `get_activity`, overriding `ttl`.
+    pub ttls: HashMap<String, humantime_serde::Serde<Duration>>,
+}
+
+impl Default for TautulliCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            ttls: HashMap::from([
+                (String::from("get_activity"), Duration::from_secs(5).into()),
+                (
+                    String::from("server_status"),
+                    Duration::from_secs(10).into(),
+                ),
+            ]),
         }
     }
 }
 
+/// Receives notifications from Tautulli's webhook agent at `/hooks/tautulli`.
+#[derive(Derivative, Deserialize, Clone, Serialize, Default)]
+#[derivative(Debug)]
+pub struct TautulliWebhookConfig {
+    /// Must match the webhook agent's Authorization header, the webhook is disabled when empty.
+    #[derivative(Debug(format_with = "obfuscated_formatter"))]
+    pub secret: String,
+    /// The bot posts recently added media and server down/up notifications to this channel.
+    pub channel_id: Option<u64>,
+}
+
 #[derive(Derivative, Deserialize, Clone, Serialize)]
 #[derivative(Debug)]
 pub struct DiscordBotConfig {
@@ -284,29 +433,178 @@ impl Default for StatUpdateConfig {
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
 pub struct UserUpdateConfig {}
 
-#[derive(Derivative, Deserialize, Clone, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WatchMedia {
+    All,
+    Movie,
+    Episode,
+}
+
+/// Hours watched of a type of media, over the last `window_days` days or all time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchHoursKey {
+    pub media: WatchMedia,
+    pub window_days: Option<u32>,
+}
+
+/// Everything about a user which can put them on a tier.
+#[derive(Debug, Default)]
+pub struct TierStanding {
+    pub plex_username: String,
+    /// The ids and names of the user's roles in the Discord server.
+    pub discord_roles: Vec<String>,
+    pub watch_hours: HashMap<WatchHoursKey, i64>,
+}
+
+impl TierStanding {
+    /// Hours watched of any media over all time.
+    pub fn total_watch_hours(&self) -> i64 {
+        self.hours(WatchHoursKey {
+            media: WatchMedia::All,
+            window_days: None,
+        })
+    }
+
+    pub fn hours(&self, key: WatchHoursKey) -> i64 {
+        self.watch_hours.get(&key).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Derivative, Deserialize, Clone, Serialize, Default)]
 #[derivative(Debug)]
 pub struct RequestLimitTier {
     pub name: String,
-    pub watch_hours: i64,
+    /// Hours of anything watched needed to earn the tier. A tier without any hours can only be
+    /// granted, by `discord_roles` or `plex_usernames`.
+    pub watch_hours: Option<i64>,
+    /// Hours of movies and episodes are added up from the user's history, so they need a
+    /// `window_days` to keep that bounded.
+    pub movie_hours: Option<i64>,
+    pub episode_hours: Option<i64>,
+    /// Only count hours watched over this many days, rather than all time.
+    pub window_days: Option<u32>,
+    /// Grant the tier to members with any of these Discord roles, by name or id.
+    #[serde(default)]
+    pub discord_roles: Vec<String>,
+    /// Always put these Plex users on the tier, even if they've earned a higher one.
+    #[serde(default)]
+    pub plex_usernames: Vec<String>,
     pub tv: RequestLimit,
     pub movie: RequestLimit,
 }
 
+impl RequestLimitTier {
+    /// The hours which have to be watched to earn the tier, all of them have to be exceeded.
+    pub fn thresholds(&self) -> Vec<(WatchHoursKey, i64)> {
+        [
+            (WatchMedia::All, self.watch_hours),
+            (WatchMedia::Movie, self.movie_hours),
+            (WatchMedia::Episode, self.episode_hours),
+        ]
+        .into_iter()
+        .filter_map(|(media, hours)| {
+            let key = WatchHoursKey {
+                media,
+                window_days: self.window_days,
+            };
+            Some((key, hours?))
+        })
+        .collect()
+    }
+
+    fn earned_by(&self, standing: &TierStanding) -> bool {
+        let thresholds = self.thresholds();
+        !thresholds.is_empty()
+            && thresholds
+                .iter()
+                .all(|&(key, hours)| standing.hours(key) > hours)
+    }
+
+    fn granted_to(&self, standing: &TierStanding) -> bool {
+        self.discord_roles
+            .iter()
+            .any(|role| standing.discord_roles.contains(role))
+    }
+
+    fn pins(&self, standing: &TierStanding) -> bool {
+        self.plex_usernames.contains(&standing.plex_username)
+    }
+}
+
+/// `tiers` are listed from lowest to highest.
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct RequestsUpgradeConfig {
     pub tiers: Vec<RequestLimitTier>,
-    pub overrides: HashMap<String, RequestLimitTier>,
+}
+
+impl RequestsUpgradeConfig {
+    pub fn validate(&self) -> Result<()> {
+        for tier in &self.tiers {
+            if (tier.movie_hours.is_some() || tier.episode_hours.is_some())
+                && tier.window_days.is_none()
+            {
+                anyhow::bail!(
+                    "tier {} sets movie_hours or episode_hours without window_days",
+                    tier.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The tier a user should be on, `None` when they haven't earned or been granted any tier.
+    ///
+    /// A tier listing the user in `plex_usernames` always wins, otherwise the user gets the
+    /// highest tier they've either earned or hold a Discord role for.
+    pub fn tier_for(&self, standing: &TierStanding) -> Option<&RequestLimitTier> {
+        self.tiers
+            .iter()
+            .find(|&tier| tier.pins(standing))
+            .or_else(|| {
+                self.tiers
+                    .iter()
+                    .rev()
+                    .find(|&tier| tier.earned_by(standing) || tier.granted_to(standing))
+            })
+    }
+
+    /// The next tier a user can earn by watching more, `None` when they're on the top tier or
+    /// have been put on a tier by username.
+    pub fn next_tier(&self, standing: &TierStanding) -> Option<&RequestLimitTier> {
+        if self.tiers.iter().any(|tier| tier.pins(standing)) {
+            return None;
+        }
+        let current = self
+            .tier_for(standing)
+            .and_then(|current| self.tiers.iter().position(|tier| tier.name == current.name));
+        self.tiers
+            .iter()
+            .skip(current.map_or(0, |current| current + 1))
+            .find(|&tier| !tier.thresholds().is_empty())
+    }
+
+    /// Every distinct set of watch hours the tiers are earned by.
+    pub fn watch_hours_keys(&self) -> HashSet<WatchHoursKey> {
+        self.tiers
+            .iter()
+            .flat_map(|tier| tier.thresholds())
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Whether any tier is granted by a Discord role, so roles need to be looked up.
+    pub fn uses_discord_roles(&self) -> bool {
+        self.tiers.iter().any(|tier| !tier.discord_roles.is_empty())
+    }
 }
 
 impl Default for RequestsUpgradeConfig {
     fn default() -> Self {
         Self {
-            overrides: HashMap::new(),
             tiers: vec![
                 RequestLimitTier {
                     name: String::from("Bronze"),
-                    watch_hours: 10,
+                    watch_hours: Some(10),
                     tv: RequestLimit {
                         quota_limit: 2,
                         quota_days: 7,
@@ -315,10 +613,11 @@ impl Default for RequestsUpgradeConfig {
                         quota_limit: 3,
                         quota_days: 7,
                     },
+                    ..Default::default()
                 },
                 RequestLimitTier {
                     name: String::from("Silver"),
-                    watch_hours: 25,
+                    watch_hours: Some(25),
                     tv: RequestLimit {
                         quota_limit: 3,
                         quota_days: 7,
@@ -327,10 +626,11 @@ impl Default for RequestsUpgradeConfig {
                         quota_limit: 7,
                         quota_days: 7,
                     },
+                    ..Default::default()
                 },
                 RequestLimitTier {
                     name: String::from("Gold"),
-                    watch_hours: 50,
+                    watch_hours: Some(50),
                     tv: RequestLimit {
                         quota_limit: 3,
                         quota_days: 3,
@@ -339,10 +639,11 @@ impl Default for RequestsUpgradeConfig {
                         quota_limit: 10,
                         quota_days: 3,
                     },
+                    ..Default::default()
                 },
                 RequestLimitTier {
                     name: String::from("Platinum"),
-                    watch_hours: 100,
+                    watch_hours: Some(100),
                     tv: RequestLimit {
                         quota_limit: 4,
                         quota_days: 3,
@@ -351,10 +652,11 @@ impl Default for RequestsUpgradeConfig {
                         quota_limit: 15,
                         quota_days: 3,
                     },
+                    ..Default::default()
                 },
                 RequestLimitTier {
                     name: String::from("Diamond"),
-                    watch_hours: 240,
+                    watch_hours: Some(240),
                     tv: RequestLimit {
                         quota_limit: 0,
                         quota_days: 0,
@@ -363,6 +665,7 @@ impl Default for RequestsUpgradeConfig {
                         quota_limit: 0,
                         quota_days: 0,
                     },
+                    ..Default::default()
                 },
             ],
         }
@@ -414,6 +717,9 @@ impl Default for LibraryCategoryConfig {
 #[derive(Deserialize, Debug, Clone, Serialize, Default)]
 pub struct WebConfig {
     pub insecure_cookie: bool,
+    /// Origins, such as `https://example.com`, which the auth flow may redirect to through its
+    /// `next` parameter. Relative paths and `discord://` links are always allowed.
+    pub allowed_redirect_origins: Vec<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -430,8 +736,108 @@ impl Default for TokenMaintenanceConfig {
     }
 }
 
+/// Roles given to members by `role-sync`, by name or id.
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct RoleSyncConfig {
+    /// Given to members whose linked Plex account has access to the Plex server.
+    pub subscriber_role: Option<String>,
+    /// The role for each request tier, keyed by the tier's name.
+    pub tier_roles: HashMap<String, String>,
+    /// How long to wait between role edits, on top of Discord's own rate limits.
+    #[serde(with = "humantime_serde")]
+    pub edit_interval: Duration,
+}
+
+impl RoleSyncConfig {
+    /// Every tier in `tier_roles` has to be one of the request tiers, so a typo doesn't leave
+    /// a role unsynced.
+    pub fn validate(&self, requests_config: &RequestsUpgradeConfig) -> Result<()> {
+        for tier_name in self.tier_roles.keys() {
+            if !requests_config
+                .tiers
+                .iter()
+                .any(|tier| &tier.name == tier_name)
+            {
+                anyhow::bail!("role_sync.tier_roles has a role for unknown tier {tier_name}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for RoleSyncConfig {
+    fn default() -> Self {
+        Self {
+            subscriber_role: None,
+            tier_roles: HashMap::new(),
+            edit_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct SchedulerConfig {
+    pub channel_refresh: JobConfig,
+    pub requests_upgrade: JobConfig,
+    pub role_sync: JobConfig,
+    pub token_maintenance: JobConfig,
+    pub user_refresh: JobConfig,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            channel_refresh: JobConfig {
+                enabled: true,
+                schedule: JobSchedule::Interval(Duration::from_secs(60 * 5)),
+                // Discord only allows a channel to be renamed twice every 10 minutes.
+                event_debounce: Some(Duration::from_secs(60 * 5)),
+            },
+            requests_upgrade: JobConfig {
+                enabled: true,
+                schedule: JobSchedule::Cron("0 0 6 * * *".into()),
+                event_debounce: None,
+            },
+            role_sync: JobConfig {
+                enabled: true,
+                schedule: JobSchedule::Cron("0 15 * * * *".into()),
+                event_debounce: None,
+            },
+            token_maintenance: JobConfig {
+                enabled: true,
+                schedule: JobSchedule::Cron("0 0 * * * *".into()),
+                event_debounce: None,
+            },
+            user_refresh: JobConfig {
+                enabled: true,
+                schedule: JobSchedule::Cron("0 30 * * * *".into()),
+                event_debounce: None,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct JobConfig {
+    pub enabled: bool,
+    pub schedule: JobSchedule,
+    /// Run early when a matching event arrives, waiting this long first so a burst of events
+    /// only runs the job once. Unset to only run on the schedule.
+    #[serde(default, with = "humantime_serde")]
+    pub event_debounce: Option<Duration>,
+}
+
+/// When a scheduled job should run, either every fixed interval (`5m`, `1h`) or on a cron
+/// expression with a leading seconds field (`0 */10 * * * *`).
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobSchedule {
+    Interval(#[serde(with = "humantime_serde")] Duration),
+    Cron(String),
+}
+
 pub fn load(path: &str) -> Result<AppConfig> {
-    Figment::new()
+    let figment = Figment::new()
         .merge(Serialized::defaults(AppConfig::default()))
         .merge(Json::file(
             PathBuf::from(path).join(format!("{PROJECT_NAME}.json")),
@@ -447,7 +853,142 @@ pub fn load(path: &str) -> Result<AppConfig> {
             Env::prefixed(&format!("{PROJECT_NAME}_"))
                 .split("__")
                 .ignore(&["database.url"]),
-        )
+        );
+    check_removed_settings(&figment)?;
+    let config: AppConfig = figment
         .extract()
-        .context("Unable to construct application configuration")
+        .context("Unable to construct application configuration")?;
+    config.requests_config.validate()?;
+    config.role_sync.validate(&config.requests_config)?;
+    Ok(config)
+}
+
+/// Fail on settings which have been replaced, rather than silently ignoring them.
+fn check_removed_settings(figment: &Figment) -> Result<()> {
+    if figment.find_value("requests_config.overrides").is_ok() {
+        anyhow::bail!(
+            "requests_config.overrides is no longer supported, list the users in the \
+             plex_usernames of the tier they should be on instead"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn standing(
+        plex_username: &str,
+        discord_roles: &[&str],
+        hours: &[(WatchHoursKey, i64)],
+    ) -> TierStanding {
+        TierStanding {
+            plex_username: String::from(plex_username),
+            discord_roles: discord_roles.iter().map(|role| role.to_string()).collect(),
+            watch_hours: hours.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn tier_for_works() {
+        let total = WatchHoursKey {
+            media: WatchMedia::All,
+            window_days: None,
+        };
+        let monthly_movies = WatchHoursKey {
+            media: WatchMedia::Movie,
+            window_days: Some(30),
+        };
+        let mut config = RequestsUpgradeConfig::default();
+        config.tiers.insert(
+            0,
+            RequestLimitTier {
+                name: String::from("Restricted"),
+                plex_usernames: vec![String::from("restricted")],
+                ..Default::default()
+            },
+        );
+        config.tiers.push(RequestLimitTier {
+            name: String::from("Supporter"),
+            discord_roles: vec![String::from("Patreon")],
+            ..Default::default()
+        });
+        config.tiers.push(RequestLimitTier {
+            name: String::from("Cinephile"),
+            movie_hours: Some(20),
+            window_days: Some(30),
+            ..Default::default()
+        });
+        let tier_name =
+            |standing: &TierStanding| config.tier_for(standing).map(|tier| tier.name.as_str());
+        let next_tier =
+            |standing: &TierStanding| config.next_tier(standing).map(|tier| tier.name.as_str());
+
+        let user = standing("user", &[], &[(total, 5)]);
+        assert_eq!(tier_name(&user), None);
+        assert_eq!(next_tier(&user), Some("Bronze"));
+
+        let user = standing("user", &[], &[(total, 30)]);
+        assert_eq!(tier_name(&user), Some("Silver"));
+        assert_eq!(next_tier(&user), Some("Gold"));
+
+        let user = standing("user", &["Patreon"], &[(total, 30)]);
+        assert_eq!(tier_name(&user), Some("Supporter"));
+        assert_eq!(next_tier(&user), Some("Cinephile"));
+
+        let user = standing("user", &[], &[(total, 30), (monthly_movies, 25)]);
+        assert_eq!(tier_name(&user), Some("Cinephile"));
+        assert_eq!(next_tier(&user), None);
+
+        let user = standing("restricted", &["Patreon"], &[(total, 300)]);
+        assert_eq!(tier_name(&user), Some("Restricted"));
+        assert_eq!(next_tier(&user), None);
+    }
+
+    #[test]
+    fn check_removed_settings_works() {
+        let figment = Figment::new().merge(Yaml::string(
+            "requests_config:\n  tiers: []\n  overrides:\n    someone:\n      name: Gold\n",
+        ));
+        assert!(check_removed_settings(&figment).is_err());
+
+        let figment = Figment::new().merge(Yaml::string("requests_config:\n  tiers: []\n"));
+        assert!(check_removed_settings(&figment).is_ok());
+    }
+
+    #[test]
+    fn validate_requires_window_for_media_hours() {
+        let mut config = RequestsUpgradeConfig {
+            tiers: vec![RequestLimitTier {
+                name: String::from("Cinephile"),
+                movie_hours: Some(50),
+                ..Default::default()
+            }],
+        };
+        assert!(config.validate().is_err());
+
+        config.tiers[0].window_days = Some(90);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_requires_known_tier_roles() {
+        let requests_config = RequestsUpgradeConfig {
+            tiers: vec![RequestLimitTier {
+                name: String::from("Gold"),
+                ..Default::default()
+            }],
+        };
+        let mut config = RoleSyncConfig {
+            tier_roles: HashMap::from([(String::from("Gold"), String::from("Gold Requests"))]),
+            ..Default::default()
+        };
+        assert!(config.validate(&requests_config).is_ok());
+
+        config
+            .tier_roles
+            .insert(String::from("Glod"), String::from("Gold Requests"));
+        assert!(config.validate(&requests_config).is_err());
+    }
 }
@@ -0,0 +1,47 @@
+use async_graphql::{
+    Enum,
+    SimpleObject,
+};
+use sea_orm::{
+    entity::prelude::*,
+    FromJsonQueryResult,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Debug, Enum, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub enum ApiKeyScope {
+    /// Run queries and subscriptions.
+    Read,
+    /// Run mutations.
+    Write,
+}
+
+/// A named API key which authenticates as an admin through `Authorization: Bearer <key>`.
+///
+/// Only a hash of the key is stored, the key itself is shown once when it is created.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
+#[graphql(name = "ApiKey")]
+#[sea_orm(table_name = "api_key")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    /// The start of the key, to tell keys apart.
+    pub key_prefix: String,
+    #[graphql(skip)]
+    #[sea_orm(unique)]
+    pub key_hash: String,
+    #[sea_orm(column_type = "Json")]
+    pub scopes: Vec<ApiKeyScope>,
+    pub created_at: DateTimeUtc,
+    pub last_used_at: Option<DateTimeUtc>,
+    pub revoked_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -15,6 +15,8 @@ pub struct Model {
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
     pub is_active: bool,
+    pub show_on_leaderboard: bool,
+    pub show_now_playing: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,59 @@
+use async_graphql::{
+    Enum,
+    SimpleObject,
+};
+use sea_orm::{
+    entity::prelude::*,
+    FromJsonQueryResult,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(
+    Debug, Enum, Copy, Eq, Deserialize, Clone, PartialEq, EnumIter, Serialize, DeriveActiveEnum,
+)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[derive(Default)]
+pub enum JobRunStatus {
+    #[sea_orm(num_value = 0)]
+    #[default]
+    Running,
+    #[sea_orm(num_value = 1)]
+    Succeeded,
+    #[sea_orm(num_value = 2)]
+    CompletedWithErrors,
+    #[sea_orm(num_value = 3)]
+    Failed,
+}
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, SimpleObject, FromJsonQueryResult,
+)]
+pub struct JobRunItemError {
+    pub item: String,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
+#[graphql(name = "JobRun")]
+#[sea_orm(table_name = "job_run")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub job: String,
+    pub status: JobRunStatus,
+    pub started_at: DateTimeUtc,
+    pub finished_at: Option<DateTimeUtc>,
+    pub items_processed: i32,
+    pub items_failed: i32,
+    pub error: Option<String>,
+    #[sea_orm(column_type = "Json")]
+    pub item_errors: Vec<JobRunItemError>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// A `/link` command waiting on the user to finish the OAuth flow, so the bot can update its reply
+/// once the Plex callback completes.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "link_request")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub discord_user_id: String,
+    pub interaction_token: String,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
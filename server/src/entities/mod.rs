@@ -1,6 +1,11 @@
 pub mod prelude;
 
+pub mod api_key;
 pub mod discord_token;
 pub mod discord_user;
+pub mod job_run;
+pub mod link_request;
 pub mod plex_token;
 pub mod plex_user;
+pub mod request_tier_assignment;
+pub mod session;
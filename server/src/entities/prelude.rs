@@ -1,8 +1,13 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
 pub use super::{
+    api_key::Entity as ApiKey,
     discord_token::Entity as DiscordToken,
     discord_user::Entity as DiscordUser,
+    job_run::Entity as JobRun,
+    link_request::Entity as LinkRequest,
     plex_token::Entity as PlexToken,
     plex_user::Entity as PlexUser,
+    request_tier_assignment::Entity as RequestTierAssignment,
+    session::Entity as Session,
 };
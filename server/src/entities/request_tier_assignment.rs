@@ -0,0 +1,28 @@
+use async_graphql::SimpleObject;
+use sea_orm::entity::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// A request tier set on an Overseerr user by `requests-upgrade`, a row is only added when the
+/// user's tier changes.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
+#[graphql(name = "RequestTierAssignment")]
+#[sea_orm(table_name = "request_tier_assignment")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub overseerr_user_id: i64,
+    pub plex_user_id: String,
+    pub plex_username: String,
+    /// The tier's name, `None` for Overseerr's default quotas.
+    pub tier: Option<String>,
+    pub watch_hours: i64,
+    pub assigned_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+use async_graphql::SimpleObject;
+use sea_orm::entity::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// A signed in browser, the session cookie only holds the session's id so it can be revoked.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
+#[graphql(name = "Session")]
+#[sea_orm(table_name = "session")]
+pub struct Model {
+    #[graphql(skip)]
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub discord_user_id: String,
+    pub plex_user_id: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::discord_user::Entity",
+        from = "Column::DiscordUserId",
+        to = "super::discord_user::Column::Id"
+    )]
+    DiscordUser,
+}
+
+impl Related<super::discord_user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DiscordUser.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
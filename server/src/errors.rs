@@ -1,30 +1,57 @@
+use sea_orm::TransactionError;
 use thiserror::Error;
 
-// Make our own error that wraps `anyhow::Error`.
+/// Errors returned by the web server, each is rendered as its own page.
 #[derive(Error, Debug)]
-#[error(transparent)]
-pub struct DisplexError(#[from] pub anyhow::Error);
+pub enum DisplexError {
+    /// The session cookie is missing or doesn't say who the user is.
+    #[error("unauthenticated")]
+    Unauthenticated,
+    /// The OAuth2 state doesn't match the one stored in the session.
+    #[error("invalid state")]
+    InvalidState,
+    /// Discord, Plex, Tautulli or Overseerr couldn't be reached or returned an error.
+    #[error("upstream unavailable: {0:#}")]
+    UpstreamUnavailable(anyhow::Error),
+    /// The Plex account doesn't have access to the Plex server.
+    #[error("{0} is not subscribed")]
+    NotSubscribed(String),
+    /// The `next` parameter isn't an allowed redirect.
+    #[error("invalid redirect to {0:?}")]
+    InvalidRedirect(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
 
 impl From<async_graphql::Error> for DisplexError {
     fn from(err: async_graphql::Error) -> Self {
-        Self(anyhow::Error::msg(err.message))
+        Self::Internal(anyhow::Error::msg(err.message))
     }
 }
 
 impl From<serde_json::Error> for DisplexError {
     fn from(err: serde_json::Error) -> Self {
-        Self(err.into())
+        Self::Internal(err.into())
     }
 }
 
 impl From<String> for DisplexError {
     fn from(err: String) -> Self {
-        Self(anyhow::Error::msg(err))
+        Self::Internal(anyhow::Error::msg(err))
     }
 }
 
 impl From<&'static str> for DisplexError {
     fn from(err: &'static str) -> Self {
-        Self(anyhow::Error::msg(err))
+        Self::Internal(anyhow::Error::msg(err))
+    }
+}
+
+impl From<TransactionError<DisplexError>> for DisplexError {
+    fn from(err: TransactionError<DisplexError>) -> Self {
+        match err {
+            TransactionError::Connection(err) => Self::Internal(err.into()),
+            TransactionError::Transaction(err) => err,
+        }
     }
 }
@@ -1,12 +1,27 @@
+use std::sync::Arc;
+
 use async_graphql::{
     dataloader::DataLoader,
+    extensions::{
+        Extension,
+        ExtensionContext,
+        ExtensionFactory,
+        NextParseQuery,
+    },
+    parser::types::{
+        ExecutableDocument,
+        OperationType,
+    },
     scalar,
     Context,
-    EmptySubscription,
     MergedObject,
+    MergedSubscription,
     Object,
     Schema,
+    ServerError,
+    ServerResult,
     SimpleObject,
+    Variables,
 };
 use sea_orm::DatabaseConnection;
 use serde::{
@@ -16,11 +31,16 @@ use serde::{
 
 use crate::{
     config::AppConfig,
+    entities::api_key::ApiKeyScope,
     server::cookies::{
         CookieData,
         Role,
     },
     services::{
+        api_key::resolver::{
+            ApiKeysMutation,
+            ApiKeysQuery,
+        },
         discord_token::resolver::{
             DiscordTokensMutation,
             DiscordTokensQuery,
@@ -29,6 +49,7 @@ use crate::{
             DiscordUsersMutation,
             DiscordUsersQuery,
         },
+        job_run::resolver::JobRunsQuery,
         plex_token::resolver::{
             PlexTokensMutation,
             PlexTokensQuery,
@@ -38,7 +59,15 @@ use crate::{
             PlexUsersQuery,
             PlexUsersService,
         },
-        tautulli::resolver::TautulliQuery,
+        request_tier_assignment::resolver::RequestTierAssignmentsQuery,
+        session::resolver::{
+            SessionsMutation,
+            SessionsQuery,
+        },
+        tautulli::resolver::{
+            TautulliQuery,
+            TautulliSubscription,
+        },
         AppServices,
     },
     AUTHOR,
@@ -127,22 +156,72 @@ impl CoreQuery {
 #[derive(MergedObject, Default)]
 pub struct QueryRoot(
     CoreQuery,
+    ApiKeysQuery,
     DiscordTokensQuery,
     DiscordUsersQuery,
+    JobRunsQuery,
     PlexTokensQuery,
     PlexUsersQuery,
+    RequestTierAssignmentsQuery,
+    SessionsQuery,
     TautulliQuery,
 );
 
 #[derive(MergedObject, Default)]
 pub struct MutationRoot(
+    ApiKeysMutation,
     DiscordTokensMutation,
     DiscordUsersMutation,
     PlexTokensMutation,
     PlexUsersMutation,
+    SessionsMutation,
 );
 
-pub type GraphqlSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+#[derive(MergedSubscription, Default)]
+pub struct SubscriptionRoot(TautulliSubscription);
+
+/// The scopes of the API key a request was authenticated with.
+pub struct ApiKeyScopes(pub Vec<ApiKeyScope>);
+
+/// Rejects operations the request's API key isn't scoped for, requests without an API key
+/// are left to the resolvers.
+struct ApiKeyScopeGuard;
+
+impl ExtensionFactory for ApiKeyScopeGuard {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ApiKeyScopeGuard)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for ApiKeyScopeGuard {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        if let Some(ApiKeyScopes(scopes)) = ctx.data_opt::<ApiKeyScopes>() {
+            for (_, operation) in document.operations.iter() {
+                let required = match operation.node.ty {
+                    OperationType::Mutation => ApiKeyScope::Write,
+                    OperationType::Query | OperationType::Subscription => ApiKeyScope::Read,
+                };
+                if !scopes.contains(&required) {
+                    return Err(ServerError::new(
+                        format!("API key is missing the {required:?} scope"),
+                        Some(operation.pos),
+                    ));
+                }
+            }
+        }
+        Ok(document)
+    }
+}
+
+pub type GraphqlSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 pub struct OrmDataloader {
     pub db: DatabaseConnection,
@@ -159,15 +238,21 @@ pub async fn get_schema(
     Schema::build(
         QueryRoot::default(),
         MutationRoot::default(),
-        EmptySubscription,
+        SubscriptionRoot::default(),
     )
     .data(config.to_owned())
     .data(db)
     .data(orm_dataloader)
+    .data(app_services.api_keys_service.clone())
     .data(app_services.discord_users_service.clone())
     .data(app_services.discord_tokens_service.clone())
     .data(app_services.plex_users_service.clone())
     .data(app_services.plex_tokens_service.clone())
+    .data(app_services.job_runs_service.clone())
+    .data(app_services.request_tier_assignments_service.clone())
+    .data(app_services.sessions_service.clone())
     .data(app_services.tautulli_service.clone())
+    .data(app_services.event_bus.clone())
+    .extension(ApiKeyScopeGuard)
     .finish()
 }
@@ -4,9 +4,11 @@ pub mod entities;
 pub mod errors;
 pub mod graphql;
 pub mod migrations;
+pub mod scheduler;
 pub mod server;
 pub mod services;
 pub mod tasks;
+pub mod telemetry;
 
 pub static VERSION: &str = env!("CARGO_PKG_VERSION");
 pub static AUTHOR: &str = "mchestr";
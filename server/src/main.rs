@@ -16,6 +16,15 @@ use displex::{
     migrations::Migrator,
     server::DisplexHttpServer,
     services::create_app_services,
+    tasks::{
+        requests_upgrade::{
+            self,
+            OutputFormat,
+        },
+        role_sync,
+        Job,
+    },
+    telemetry,
 };
 use sea_orm::{
     Database,
@@ -43,8 +52,23 @@ struct Cli {
 enum Commands {
     Bot,
     ChannelRefresh,
+    /// Run the server, the bot and every scheduled task in a single process
+    Daemon,
     Metadata,
-    RequestsUpgrade,
+    RequestsUpgrade {
+        /// Print which users would move tier instead of updating Overseerr
+        #[arg(long)]
+        dry_run: bool,
+        /// Output format for --dry-run
+        #[arg(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Give or remove the configured subscriber and request tier roles in Discord
+    RoleSync {
+        /// Print the role changes which would be made instead of making them
+        #[arg(long)]
+        dry_run: bool,
+    },
     Server,
     TokenMaintenance,
     UserRefresh,
@@ -75,10 +99,10 @@ async fn main() -> Result<()> {
     if dotenvy::dotenv().is_err() {
         println!("no .env found.");
     }
-    tracing_subscriber::fmt::init();
 
     let args = Cli::parse();
     let config = config::load(&args.config_dir)?;
+    telemetry::init_tracing(&config.tracing)?;
     tracing::debug!("{:#?}", config);
 
     let database_url = generate_database_url(&config);
@@ -114,16 +138,55 @@ async fn main() -> Result<()> {
 
     match args.command {
         Commands::Bot => {
-            config.discord_bot.type_.run(rx, serenity_client).await?;
+            tokio::try_join!(
+                config
+                    .discord_bot
+                    .type_
+                    .run(rx.resubscribe(), serenity_client, &app_services),
+                displex::scheduler::run_jobs(rx, &config, &app_services, &[Job::RoleSync]),
+            )?;
         }
         Commands::ChannelRefresh => {
-            displex::tasks::channel_refresh::run(&config, &app_services).await?;
+            Job::ChannelRefresh.run(&config, &app_services).await?;
+        }
+        Commands::Daemon => {
+            tokio::try_join!(
+                config
+                    .http
+                    .type_
+                    .run(rx.resubscribe(), config.clone(), &app_services, &schema),
+                config
+                    .discord_bot
+                    .type_
+                    .run(rx.resubscribe(), serenity_client, &app_services),
+                displex::scheduler::run(rx, &config, &app_services),
+            )?;
         }
         Commands::Metadata => {
             displex::tasks::metadata::run(&config).await?;
         }
-        Commands::RequestsUpgrade => {
-            displex::tasks::requests_upgrade::run(&app_services).await?;
+        Commands::RequestsUpgrade { dry_run, format } => {
+            if dry_run {
+                let report =
+                    requests_upgrade::dry_run(&config.requests_config, &app_services).await?;
+                println!("{}", report.render(format)?);
+            } else {
+                Job::RequestsUpgrade.run(&config, &app_services).await?;
+            }
+        }
+        Commands::RoleSync { dry_run } => {
+            if dry_run {
+                let plan = role_sync::plan(&config, &app_services).await?;
+                for change in &plan.changes {
+                    println!("{change}");
+                }
+                println!("{} role changes planned", plan.changes.len());
+                for err in &plan.errors {
+                    println!("unable to check roles of {}: {}", err.item, err.error);
+                }
+            } else {
+                Job::RoleSync.run(&config, &app_services).await?;
+            }
         }
         Commands::Server => {
             config
@@ -133,11 +196,12 @@ async fn main() -> Result<()> {
                 .await?;
         }
         Commands::TokenMaintenance => {
-            displex::tasks::token_maintenance::run(&config, &app_services).await?;
+            Job::TokenMaintenance.run(&config, &app_services).await?;
         }
         Commands::UserRefresh => {
-            displex::tasks::user_refresh::run(&config, &app_services).await?;
+            Job::UserRefresh.run(&config, &app_services).await?;
         }
     }
+    telemetry::shutdown_tracing();
     Ok(())
 }
@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(JobRun::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(JobRun::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JobRun::Job).string().not_null())
+                    .col(
+                        ColumnDef::new(JobRun::Status)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(JobRun::StartedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(JobRun::FinishedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(JobRun::ItemsProcessed)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(JobRun::ItemsFailed)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(JobRun::Error).text())
+                    .col(ColumnDef::new(JobRun::ItemErrors).json().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-job-run-job-started_at")
+                    .table(JobRun::Table)
+                    .col(JobRun::Job)
+                    .col(JobRun::StartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JobRun::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum JobRun {
+    Table,
+    Id,
+    Job,
+    Status,
+    StartedAt,
+    FinishedAt,
+    ItemsProcessed,
+    ItemsFailed,
+    Error,
+    ItemErrors,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LinkRequest::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LinkRequest::DiscordUserId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(LinkRequest::InteractionToken)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(LinkRequest::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(LinkRequest::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LinkRequest::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum LinkRequest {
+    Table,
+    DiscordUserId,
+    InteractionToken,
+    CreatedAt,
+    ExpiresAt,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+use crate::migrations::DiscordUser;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DiscordUser::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("show_on_leaderboard"))
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DiscordUser::Table)
+                    .drop_column(Alias::new("show_on_leaderboard"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
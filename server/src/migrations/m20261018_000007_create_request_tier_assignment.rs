@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RequestTierAssignment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RequestTierAssignment::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RequestTierAssignment::OverseerrUserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RequestTierAssignment::PlexUserId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RequestTierAssignment::PlexUsername)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RequestTierAssignment::Tier).string())
+                    .col(
+                        ColumnDef::new(RequestTierAssignment::WatchHours)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RequestTierAssignment::AssignedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-request-tier-assignment-plex_user_id")
+                    .table(RequestTierAssignment::Table)
+                    .col(RequestTierAssignment::PlexUserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RequestTierAssignment::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum RequestTierAssignment {
+    Table,
+    Id,
+    OverseerrUserId,
+    PlexUserId,
+    PlexUsername,
+    Tier,
+    WatchHours,
+    AssignedAt,
+}
@@ -7,6 +7,13 @@ mod m20230528_193841_create_plex_token;
 mod m20230930_035233_discord_user_is_active;
 mod m20231007_195159_add_token_enum;
 mod m20231007_222508_add_token_enum;
+mod m20261018_000001_create_job_run;
+mod m20261018_000002_create_link_request;
+mod m20261018_000003_discord_user_show_on_leaderboard;
+mod m20261018_000004_discord_user_show_now_playing;
+mod m20261018_000005_create_api_key;
+mod m20261018_000006_create_session;
+mod m20261018_000007_create_request_tier_assignment;
 
 pub use m20220101_000001_create_discord_user::DiscordUser;
 pub use m20230528_193818_create_discord_token::DiscordToken;
@@ -26,6 +33,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20230930_035233_discord_user_is_active::Migration),
             Box::new(m20231007_195159_add_token_enum::Migration),
             Box::new(m20231007_222508_add_token_enum::Migration),
+            Box::new(m20261018_000001_create_job_run::Migration),
+            Box::new(m20261018_000002_create_link_request::Migration),
+            Box::new(m20261018_000003_discord_user_show_on_leaderboard::Migration),
+            Box::new(m20261018_000004_discord_user_show_now_playing::Migration),
+            Box::new(m20261018_000005_create_api_key::Migration),
+            Box::new(m20261018_000006_create_session::Migration),
+            Box::new(m20261018_000007_create_request_tier_assignment::Migration),
         ]
     }
 }
@@ -0,0 +1,224 @@
+use std::{
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{
+    Context,
+    Result,
+};
+use chrono::Utc;
+use tokio::{
+    sync::broadcast::{
+        error::RecvError,
+        Receiver,
+    },
+    task::JoinSet,
+    time::{
+        Interval,
+        MissedTickBehavior,
+    },
+};
+
+use crate::{
+    config::{
+        AppConfig,
+        JobSchedule,
+    },
+    services::AppServices,
+    tasks::Job,
+};
+
+enum Ticker {
+    Interval(Interval),
+    Cron(Box<cron::Schedule>),
+}
+
+impl Ticker {
+    fn new(schedule: &JobSchedule) -> Result<Self> {
+        Ok(match schedule {
+            JobSchedule::Interval(period) => {
+                if period.is_zero() {
+                    anyhow::bail!("interval must be greater than zero");
+                }
+                let mut interval = tokio::time::interval(*period);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                Ticker::Interval(interval)
+            }
+            JobSchedule::Cron(expression) => Ticker::Cron(Box::new(
+                cron::Schedule::from_str(expression)
+                    .with_context(|| format!("invalid cron expression {expression:?}"))?,
+            )),
+        })
+    }
+
+    /// Push the next interval tick back a full period, cron schedules are left as they are.
+    fn reset(&mut self) {
+        if let Ticker::Interval(interval) = self {
+            interval.reset();
+        }
+    }
+
+    async fn tick(&mut self) {
+        match self {
+            Ticker::Interval(interval) => {
+                interval.tick().await;
+            }
+            Ticker::Cron(schedule) => {
+                let now = Utc::now();
+                match schedule.after(&now).next() {
+                    Some(next) => {
+                        tokio::time::sleep((next - now).to_std().unwrap_or_default()).await
+                    }
+                    None => std::future::pending().await,
+                }
+            }
+        }
+    }
+}
+
+/// Run every enabled job on its configured schedule until a shutdown signal is received.
+pub async fn run(kill: Receiver<()>, config: &AppConfig, services: &AppServices) -> Result<()> {
+    run_jobs(
+        kill,
+        config,
+        services,
+        &[
+            Job::ChannelRefresh,
+            Job::RequestsUpgrade,
+            Job::RoleSync,
+            Job::TokenMaintenance,
+            Job::UserRefresh,
+        ],
+    )
+    .await
+}
+
+/// Run the given jobs which are enabled on their configured schedule until a shutdown signal is
+/// received, e.g. `role-sync` alongside the bot.
+pub async fn run_jobs(
+    kill: Receiver<()>,
+    config: &AppConfig,
+    services: &AppServices,
+    jobs: &[Job],
+) -> Result<()> {
+    let scheduler = &config.scheduler;
+    let mut handles = JoinSet::new();
+    for &job in jobs {
+        let job_config = match job {
+            Job::ChannelRefresh => &scheduler.channel_refresh,
+            Job::RequestsUpgrade => &scheduler.requests_upgrade,
+            Job::RoleSync => &scheduler.role_sync,
+            Job::TokenMaintenance => &scheduler.token_maintenance,
+            Job::UserRefresh => &scheduler.user_refresh,
+        };
+        if !job_config.enabled {
+            tracing::info!("{job} is disabled, not scheduling");
+            continue;
+        }
+        let ticker = Ticker::new(&job_config.schedule)
+            .with_context(|| format!("invalid schedule for {job}"))?;
+        tracing::info!("scheduling {job}: {:?}", job_config.schedule);
+        handles.spawn(run_job(
+            job,
+            ticker,
+            job_config.event_debounce,
+            kill.resubscribe(),
+            config.clone(),
+            services.clone(),
+        ));
+    }
+
+    while handles.join_next().await.is_some() {}
+    tracing::info!("scheduler stopped");
+    Ok(())
+}
+
+async fn run_job(
+    job: Job,
+    mut ticker: Ticker,
+    event_debounce: Option<Duration>,
+    mut kill: Receiver<()>,
+    config: AppConfig,
+    services: AppServices,
+) {
+    // The sender lives in `services`, so this never reports the bus as closed.
+    let mut events = services.event_bus.subscribe();
+    let debounce = event_debounce.unwrap_or_default();
+    loop {
+        tokio::select! {
+            biased;
+            _ = kill.recv() => break,
+            _ = ticker.tick() => {},
+            event = events.recv(), if event_debounce.is_some() => match event {
+                Ok(event) if job.triggered_by(&event) => {
+                    tracing::info!("{job} triggered by {event:?}");
+                    tokio::select! {
+                        _ = kill.recv() => break,
+                        _ = tokio::time::sleep(debounce) => {},
+                    }
+                    while events.try_recv().is_ok() {}
+                    // Push back the next scheduled run so it doesn't follow straight after this one.
+                    ticker.reset();
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("{job} missed {skipped} events");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+
+        // Each run is awaited before waiting on the next tick, so a job never overlaps with
+        // itself; ticks missed while it was running are skipped rather than queued up.
+        tracing::info!("running scheduled job {job}");
+        let config = config.clone();
+        let services = services.clone();
+        match tokio::spawn(async move { job.run(&config, &services).await }).await {
+            Ok(Ok(report)) => tracing::info!(
+                "{job} finished: {} processed, {} failed",
+                report.items_processed,
+                report.item_errors.len()
+            ),
+            Ok(Err(err)) => tracing::error!("{job} failed: {err:?}"),
+            Err(err) => tracing::error!("{job} panicked: {err}"),
+        }
+    }
+    tracing::info!("stopped scheduling {job}");
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::config::JobConfig;
+
+    #[test]
+    fn schedule_serde_works() {
+        let schedule: JobSchedule = serde_json::from_str("{\"interval\":\"5m\"}").unwrap();
+        assert!(matches!(schedule, JobSchedule::Interval(d) if d == Duration::from_secs(300)));
+
+        let schedule: JobSchedule = serde_json::from_str("{\"cron\":\"0 0 * * * *\"}").unwrap();
+        assert!(matches!(schedule, JobSchedule::Cron(ref c) if c == "0 0 * * * *"));
+
+        let job_config: JobConfig = serde_json::from_str(
+            "{\"enabled\":true,\"schedule\":{\"interval\":\"5m\"},\"event_debounce\":\"10m\"}",
+        )
+        .unwrap();
+        assert_eq!(job_config.event_debounce, Some(Duration::from_secs(600)));
+
+        let job_config: JobConfig =
+            serde_json::from_str("{\"enabled\":true,\"schedule\":{\"interval\":\"5m\"}}").unwrap();
+        assert_eq!(job_config.event_debounce, None);
+    }
+
+    #[tokio::test]
+    async fn ticker_rejects_invalid_schedules() {
+        assert!(Ticker::new(&JobSchedule::Cron("every tuesday".into())).is_err());
+        assert!(Ticker::new(&JobSchedule::Interval(Duration::ZERO)).is_err());
+        assert!(Ticker::new(&JobSchedule::Cron("0 */10 * * * *".into())).is_ok());
+        assert!(Ticker::new(&JobSchedule::Interval(Duration::from_secs(60))).is_ok());
+    }
+}
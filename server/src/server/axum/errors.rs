@@ -1,6 +1,7 @@
 use axum::{
     http::StatusCode,
     response::{
+        Html,
         IntoResponse,
         Response,
     },
@@ -8,10 +9,127 @@ use axum::{
 
 use crate::errors::DisplexError;
 
+/// Shown on every error page, as linking is the only flow users go through in their browser.
+const RESTART_LINK: &str =
+    "To try again, run <code>/link</code> in Discord and follow the new link.";
+
 // Tell axum how to convert `DisplexError` into a response.
 impl IntoResponse for DisplexError {
     fn into_response(self) -> Response {
-        tracing::error!("{:?}", self);
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", self.0)).into_response()
+        let (status, title, message) = match &self {
+            DisplexError::Unauthenticated => {
+                tracing::info!("{self}");
+                (
+                    StatusCode::UNAUTHORIZED,
+                    "Session Expired",
+                    "We couldn't find your sign in session, it may have expired or your browser may be blocking cookies.",
+                )
+            }
+            DisplexError::InvalidState => {
+                tracing::info!("{self}");
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Sign In Expired",
+                    "This sign in link has already been used or was started in another browser.",
+                )
+            }
+            DisplexError::UpstreamUnavailable(_) => {
+                tracing::error!("{self:?}");
+                (
+                    StatusCode::BAD_GATEWAY,
+                    "Service Unavailable",
+                    "We couldn't reach Discord or Plex to finish linking your account, please try again in a few minutes.",
+                )
+            }
+            DisplexError::NotSubscribed(_) => {
+                tracing::info!("{self}");
+                (
+                    StatusCode::FORBIDDEN,
+                    "Not A Member",
+                    "The Plex account you signed in with doesn't have access to our Plex server. If you have another Plex account, sign out of Plex first.",
+                )
+            }
+            DisplexError::InvalidRedirect(_) => {
+                tracing::warn!("{self}");
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Invalid Link",
+                    "The link you followed tried to send you somewhere we don't recognise.",
+                )
+            }
+            DisplexError::Internal(_) => {
+                tracing::error!("{self:?}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Something Went Wrong",
+                    "We were unable to link your account.",
+                )
+            }
+        };
+        error_page(status, title, message)
+    }
+}
+
+/// A standalone HTML page for errors users hit in their browser during the link flow.
+fn error_page(status: StatusCode, title: &str, message: &str) -> Response {
+    (
+        status,
+        Html(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>{title}</title>
+  <style>
+    body {{ font-family: sans-serif; background: #1e1f22; color: #dbdee1; display: flex; justify-content: center; padding-top: 10vh; }}
+    main {{ max-width: 32rem; padding: 2rem; background: #2b2d31; border-radius: 8px; }}
+    h1 {{ color: #e5a00d; }}
+  </style>
+</head>
+<body>
+  <main>
+    <h1>{title}</h1>
+    <p>{message}</p>
+    <p>{RESTART_LINK}</p>
+  </main>
+</body>
+</html>"#
+        )),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn into_response_status_works() {
+        let cases = [
+            (DisplexError::Unauthenticated, StatusCode::UNAUTHORIZED),
+            (DisplexError::InvalidState, StatusCode::BAD_REQUEST),
+            (
+                DisplexError::UpstreamUnavailable(anyhow!("timed out")),
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                DisplexError::NotSubscribed(String::from("user")),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                DisplexError::InvalidRedirect(String::from("https://evil.com")),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                DisplexError::Internal(anyhow!("secret details")),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ];
+        for (err, status) in cases {
+            assert_eq!(err.into_response().status(), status);
+        }
     }
 }
@@ -1,5 +1,9 @@
 use axum::{
-    extract::Request,
+    extract::{
+        ConnectInfo,
+        Request,
+    },
+    middleware,
     Router,
 };
 
@@ -24,6 +28,7 @@ use crate::{
     config::AppConfig,
     graphql::GraphqlSchema,
     services::AppServices,
+    telemetry,
 };
 
 mod errors;
@@ -59,8 +64,13 @@ pub async fn run(
         .allow_credentials(true);
 
     let addr = format!("{}:{}", &config.http.host, &config.http.port);
-    let app = Router::new()
-        .merge(routes::configure(&config))
+    let mut router = Router::new().merge(routes::configure(&config));
+    if config.metrics.enabled {
+        // Install the recorder up front so requests are counted from the start.
+        telemetry::prometheus();
+        router = router.layer(middleware::from_fn(telemetry::track_http));
+    }
+    let app = router
         .with_state(DisplexState {
             config,
             services: services.clone(),
@@ -111,13 +121,17 @@ pub async fn run(
             // Hyper also has its own `Service` trait and doesn't use tower. We can use
             // `hyper::service::service_fn` to create a hyper `Service` that calls our app through
             // `tower::Service::call`.
-            let hyper_service = hyper::service::service_fn(move |request: Request<Incoming>| {
-                // We have to clone `tower_service` because hyper's `Service` uses `&self` whereas
-                // tower's `Service` requires `&mut self`.
-                //
-                // We don't need to call `poll_ready` since `Router` is always ready.
-                tower_service.clone().call(request)
-            });
+            let hyper_service =
+                hyper::service::service_fn(move |mut request: Request<Incoming>| {
+                    // Serving connections ourselves skips `into_make_service_with_connect_info`, so
+                    // provide `ConnectInfo` to the handlers here.
+                    request.extensions_mut().insert(ConnectInfo(remote_addr));
+                    // We have to clone `tower_service` because hyper's `Service` uses `&self`
+                    // whereas tower's `Service` requires `&mut self`.
+                    //
+                    // We don't need to call `poll_ready` since `Router` is always ready.
+                    tower_service.clone().call(request)
+                });
 
             // `hyper_util::server::conn::auto::Builder` supports both http1 and http2 but doesn't
             // support graceful so we have to use hyper directly and unfortunately pick between
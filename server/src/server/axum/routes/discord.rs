@@ -1,14 +1,23 @@
 use anyhow::anyhow;
-use std::time::Duration;
+use std::{
+    net::SocketAddr,
+    time::Duration,
+};
 
 use axum::{
     extract::{
+        ConnectInfo,
         Query,
         State,
     },
+    http::{
+        header::USER_AGENT,
+        HeaderMap,
+    },
     response::{
         IntoResponse,
         Redirect,
+        Response,
     },
     routing::get,
     Router,
@@ -19,6 +28,11 @@ use sea_orm::TransactionTrait;
 use serde::Deserialize;
 use tower_cookies::Cookies;
 
+use super::{
+    check_redirect,
+    client_ip,
+    redirect_next,
+};
 use crate::{
     errors::DisplexError,
     server::{
@@ -27,7 +41,6 @@ use crate::{
             get_cookie_data,
             set_cookie_data,
             CookieData,
-            Role,
         },
     },
     services::{
@@ -48,7 +61,8 @@ async fn discord_auth(
     cookies: Cookies,
     State(state): State<DisplexState>,
     query_string: Query<DiscordAuthQueryParams>,
-) -> Result<impl IntoResponse, DisplexError> {
+) -> Result<Response, DisplexError> {
+    check_redirect(&state.config.web, query_string.next.as_deref())?;
     let query_params = match &query_string.next {
         Some(next) => format!("?next={}", next),
         None => String::new(),
@@ -61,16 +75,21 @@ async fn discord_auth(
     ));
 
     let persist_state = String::from(persist_state.secret());
+    // Keep the current session id, so the callback can delete the session it replaces.
+    let session_id = get_cookie_data(&state.config.session.secret_key, &cookies)
+        .ok()
+        .and_then(|cookie_data| cookie_data.session_id);
     set_cookie_data(
-        &state.config.session.secret_key,
+        &state.config.session,
         &cookies,
         &CookieData {
             discord_state: Some(persist_state),
+            session_id,
             ..Default::default()
         },
     )?;
 
-    Ok(Redirect::to(url.as_str()))
+    Ok(Redirect::to(url.as_str()).into_response())
 }
 
 #[derive(Deserialize)]
@@ -82,16 +101,27 @@ struct CallbackQueryParams {
 
 async fn callback(
     cookies: Cookies,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     State(state): State<DisplexState>,
     query_string: Query<CallbackQueryParams>,
-) -> Result<impl IntoResponse, DisplexError> {
-    let mut cookie_data = get_cookie_data(&state.config.session.secret_key, &cookies)?;
-
-    let discord_state = cookie_data.discord_state.as_mut().unwrap();
+) -> Result<Response, DisplexError> {
+    check_redirect(&state.config.web, query_string.next.as_deref())?;
+    let cookie_data = get_cookie_data(&state.config.session.secret_key, &cookies)
+        .map_err(|_| DisplexError::Unauthenticated)?;
+
+    let discord_state = cookie_data
+        .discord_state
+        .as_deref()
+        .ok_or(DisplexError::InvalidState)?;
     verify_state(discord_state, &query_string.state)?;
 
     let query_params = match &query_string.next {
-        Some(next) => format!("?next={}", urlencoding::decode(next).unwrap()),
+        Some(next) => format!(
+            "?next={}",
+            urlencoding::decode(next)
+                .map_err(|_| DisplexError::InvalidRedirect(next.to_owned()))?
+        ),
         None => String::new(),
     };
     tracing::debug!("query_params:{}", query_params);
@@ -105,9 +135,22 @@ async fn callback(
                 &state.config.http.hostname, query_params
             ),
         )
-        .await?;
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
     let access_token = String::from(token.access_token().secret());
-    let discord_user = state.services.discord_service.user(&access_token).await?;
+    let refresh_token = token
+        .refresh_token()
+        .ok_or_else(|| {
+            DisplexError::UpstreamUnavailable(anyhow!("Discord did not return a refresh token"))
+        })?
+        .secret()
+        .to_owned();
+    let discord_user = state
+        .services
+        .discord_service
+        .user(&access_token)
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
 
     state
         .services
@@ -123,7 +166,7 @@ async fn callback(
                 match result {
                     CreateDiscordUserResult::Error(err) => match err.error {
                         CreateDiscordUserErrorVariant::InternalError => {
-                            Err(DisplexError(anyhow!("internal error")))
+                            Err(DisplexError::Internal(anyhow!("internal error")))
                         }
                     },
                     _ => Ok(()),
@@ -137,10 +180,7 @@ async fn callback(
                     .discord_tokens_service
                     .create_with_conn(
                         token.access_token().secret(),
-                        token
-                            .refresh_token()
-                            .expect("expecting refresh token")
-                            .secret(),
+                        &refresh_token,
                         &(chrono::Utc::now()
                             + chrono::Duration::seconds(
                                 token
@@ -155,42 +195,53 @@ async fn callback(
                     .await?;
                 match result {
                     CreateDiscordTokenResult::Error(_) => {
-                        Err(DisplexError(anyhow!("internal error")))
+                        Err(DisplexError::Internal(anyhow!("internal error")))
                     }
                     _ => Ok(()),
                 }?;
                 Ok(())
             })
         })
-        .await
-        .expect("failed to save discord user info to database");
-
-    let discord_user_id = discord_user.id;
-    cookie_data.discord_user = Some(discord_user_id.clone());
-    if state
-        .config
-        .api
-        .admin_discord_ids
-        .contains(&discord_user_id.clone())
-    {
-        cookie_data.role = Role::Admin;
-    } else {
-        cookie_data.role = Role::User;
+        .await?;
+
+    // Replace any previous session, so signing in again can't reuse a session id.
+    if let Some(session_id) = &cookie_data.session_id {
+        state.services.sessions_service.delete(session_id).await?;
     }
-    set_cookie_data(&state.config.session.secret_key, &cookies, &cookie_data)?;
+    let session = state
+        .services
+        .sessions_service
+        .create(
+            &discord_user.id,
+            headers
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+            client_ip(
+                &headers,
+                connect_info,
+                state.config.http.trust_forwarded_for,
+            ),
+            state.config.session.ttl,
+        )
+        .await?;
+    set_cookie_data(
+        &state.config.session,
+        &cookies,
+        &CookieData {
+            session_id: Some(session.id),
+            ..Default::default()
+        },
+    )?;
 
-    let url = match &query_string.next {
-        Some(next) => next,
-        None => "/",
-    };
-    Ok(Redirect::to(url))
+    redirect_next(&state.config.web, query_string.next.as_deref())
 }
 
 #[tracing::instrument]
-fn verify_state(session_state: &str, query_string_state: &str) -> Result<(), anyhow::Error> {
+fn verify_state(session_state: &str, query_string_state: &str) -> Result<(), DisplexError> {
     if session_state != query_string_state {
         tracing::info!("session state does not match query parameters");
-        anyhow::bail!("invalid state")
+        return Err(DisplexError::InvalidState);
     }
     Ok(())
 }
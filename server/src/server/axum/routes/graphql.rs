@@ -1,16 +1,31 @@
-use async_graphql::http::{
-    playground_source,
-    GraphQLPlaygroundConfig,
+use async_graphql::{
+    http::{
+        playground_source,
+        GraphQLPlaygroundConfig,
+        ALL_WEBSOCKET_PROTOCOLS,
+    },
+    Data,
 };
 use async_graphql_axum::{
+    GraphQLProtocol,
     GraphQLRequest,
     GraphQLResponse,
+    GraphQLWebSocket,
 };
 use axum::{
-    extract::State,
+    extract::{
+        State,
+        WebSocketUpgrade,
+    },
+    http::{
+        header,
+        HeaderMap,
+        StatusCode,
+    },
     response::{
         Html,
         IntoResponse,
+        Response,
     },
     routing::get,
     Router,
@@ -19,30 +34,127 @@ use axum::{
 use tower_cookies::Cookies;
 
 use crate::{
-    errors::DisplexError,
+    entities::api_key::ApiKeyScope,
+    graphql::ApiKeyScopes,
     server::{
         axum::DisplexState,
-        cookies::get_cookie_data,
+        cookies::{
+            get_session_data,
+            CookieData,
+            Role,
+        },
     },
 };
 
+use super::secret_matches;
+
 async fn graphql_playground() -> impl IntoResponse {
     Html(playground_source(
-        GraphQLPlaygroundConfig::new("/").subscription_endpoint("/ws"),
+        GraphQLPlaygroundConfig::new("/gql").subscription_endpoint("/gql/ws"),
     ))
 }
 
+/// Authenticate the request by its `Authorization: Bearer <key>` header, if it has one.
+///
+/// The configured `api.api_key` is granted every scope, otherwise the key must be an unrevoked
+/// managed API key.
+async fn authenticate_api_key(
+    state: &DisplexState,
+    headers: &HeaderMap,
+) -> Result<Option<ApiKeyScopes>, StatusCode> {
+    let Some(authorization) = headers.get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let key = authorization
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if secret_matches(key.as_bytes(), &state.config.api.api_key) {
+        return Ok(Some(ApiKeyScopes(vec![
+            ApiKeyScope::Read,
+            ApiKeyScope::Write,
+        ])));
+    }
+    match state.services.api_keys_service.authenticate(key).await {
+        Ok(Some(api_key)) => {
+            tracing::debug!("authenticated with API key {}", api_key.name);
+            Ok(Some(ApiKeyScopes(api_key.scopes)))
+        }
+        Ok(None) => Err(StatusCode::UNAUTHORIZED),
+        Err(err) => {
+            tracing::error!("unable to authenticate API key: {:?}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Build the request data, API keys act as an admin limited to the key's scopes.
+async fn request_data(
+    state: &DisplexState,
+    headers: &HeaderMap,
+    cookies: &Cookies,
+) -> Result<(CookieData, Option<ApiKeyScopes>), StatusCode> {
+    Ok(match authenticate_api_key(state, headers).await? {
+        Some(scopes) => (
+            CookieData {
+                role: Role::Admin,
+                ..Default::default()
+            },
+            Some(scopes),
+        ),
+        None => (get_session_data(&state.services, cookies).await, None),
+    })
+}
+
 async fn graphql_handler(
     State(state): State<DisplexState>,
+    headers: HeaderMap,
     cookies: Cookies,
     req: GraphQLRequest,
-) -> Result<GraphQLResponse, DisplexError> {
-    let cookie = get_cookie_data(&state.config.session.secret_key, &cookies).unwrap_or_default();
+) -> Response {
+    let (cookie, scopes) = match request_data(&state, &headers, &cookies).await {
+        Ok(data) => data,
+        Err(status) => return status.into_response(),
+    };
     let mut req = req.into_inner();
-    req = req.data(cookie);
-    Ok(state.schema.execute(req).await.into())
+    req = req.data(cookie).data(cookies);
+    if let Some(scopes) = scopes {
+        req = req.data(scopes);
+    }
+    GraphQLResponse::from(state.schema.execute(req).await).into_response()
+}
+
+async fn graphql_ws_handler(
+    State(state): State<DisplexState>,
+    headers: HeaderMap,
+    cookies: Cookies,
+    protocol: GraphQLProtocol,
+    websocket: WebSocketUpgrade,
+) -> Response {
+    let (cookie, scopes) = match request_data(&state, &headers, &cookies).await {
+        Ok(data) => data,
+        Err(status) => return status.into_response(),
+    };
+    let mut data = Data::default();
+    data.insert(cookie);
+    if let Some(scopes) = scopes {
+        data.insert(scopes);
+    }
+    websocket
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, state.schema, protocol)
+                .with_data(data)
+                .serve()
+        })
 }
 
 pub fn routes() -> Router<DisplexState> {
-    Router::new().route("/", get(graphql_playground).post(graphql_handler))
+    Router::new()
+        .route("/", get(graphql_playground).post(graphql_handler))
+        .route("/ws", get(graphql_ws_handler))
 }
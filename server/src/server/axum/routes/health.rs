@@ -0,0 +1,171 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    time::Duration,
+};
+
+use anyhow::Result;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+    routing::get,
+    Json,
+    Router,
+};
+use http::StatusCode;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    config::Dependency,
+    server::axum::DisplexState,
+};
+
+/// How long a dependency has to respond before it's reported as down, so a hung dependency can't
+/// hold up the readiness probe.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Up,
+    Down,
+    /// The dependency isn't used by this process, e.g. the bot in the `server` subcommand.
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    status: Status,
+    required: bool,
+    /// Why the dependency is down. Only a generic reason is given, as the endpoint isn't
+    /// authenticated and the underlying errors can include URLs and connection details.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+}
+
+/// The dependency responded, but isn't connected to the service it depends on.
+#[derive(Debug, Error)]
+#[error("{0}")]
+struct NotConnected(&'static str);
+
+#[derive(Debug, Serialize)]
+struct Readiness {
+    ready: bool,
+    checks: BTreeMap<Dependency, Check>,
+}
+
+impl Readiness {
+    fn new(checks: BTreeMap<Dependency, Check>) -> Self {
+        Self {
+            ready: checks
+                .values()
+                .all(|check| !check.required || check.status != Status::Down),
+            checks,
+        }
+    }
+}
+
+async fn healthz() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+async fn check(
+    required: &[Dependency],
+    dependency: Dependency,
+    probe: impl Future<Output = Result<Option<()>>>,
+) -> (Dependency, Check) {
+    let (status, error) = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(Ok(Some(()))) => (Status::Up, None),
+        Ok(Ok(None)) => (Status::Skipped, None),
+        Ok(Err(err)) => {
+            tracing::warn!("{dependency:?} is down: {err:#}");
+            match err.is::<NotConnected>() {
+                true => (Status::Down, Some("not connected")),
+                false => (Status::Down, Some("unreachable")),
+            }
+        }
+        Err(_) => {
+            tracing::warn!("{dependency:?} is down: timed out after {CHECK_TIMEOUT:?}");
+            (Status::Down, Some("timed out"))
+        }
+    };
+    (
+        dependency,
+        Check {
+            status,
+            required: required.contains(&dependency),
+            error,
+        },
+    )
+}
+
+async fn readyz(State(state): State<DisplexState>) -> impl IntoResponse {
+    let services = &state.services;
+    let required = &state.config.health.required;
+    let checks = tokio::join!(
+        check(required, Dependency::Database, async {
+            services.db.ping().await?;
+            Ok(Some(()))
+        }),
+        check(required, Dependency::Tautulli, async {
+            match services.tautulli_service.server_status().await?.connected {
+                true => Ok(Some(())),
+                false => Err(NotConnected("Tautulli is not connected to the Plex server").into()),
+            }
+        }),
+        check(required, Dependency::Overseerr, async {
+            services.overseerr_service.status().await?;
+            Ok(Some(()))
+        }),
+        check(required, Dependency::Discord, async {
+            match services.discord_service.gateway_connected().await {
+                Some(true) => Ok(Some(())),
+                Some(false) => Err(NotConnected("bot is not connected to the gateway").into()),
+                None => Ok(None),
+            }
+        }),
+    );
+    let readiness = Readiness::new(BTreeMap::from([checks.0, checks.1, checks.2, checks.3]));
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+pub fn routes() -> Router<DisplexState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(status: Status, required: bool) -> Check {
+        Check {
+            status,
+            required,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn readiness_works() {
+        let readiness = Readiness::new(BTreeMap::from([
+            (Dependency::Database, check(Status::Up, true)),
+            (Dependency::Overseerr, check(Status::Down, false)),
+            (Dependency::Discord, check(Status::Skipped, true)),
+        ]));
+        assert!(readiness.ready);
+
+        let readiness = Readiness::new(BTreeMap::from([
+            (Dependency::Database, check(Status::Down, true)),
+            (Dependency::Tautulli, check(Status::Up, true)),
+        ]));
+        assert!(!readiness.ready);
+    }
+}
@@ -0,0 +1,46 @@
+use axum::{
+    extract::State,
+    http::{
+        header,
+        HeaderMap,
+        StatusCode,
+    },
+    response::{
+        IntoResponse,
+        Response,
+    },
+    routing::get,
+    Router,
+};
+
+use crate::{
+    server::axum::DisplexState,
+    telemetry,
+};
+
+use super::secret_matches;
+
+async fn metrics(State(state): State<DisplexState>, headers: HeaderMap) -> Response {
+    let bearer_token = &state.config.metrics.bearer_token;
+    if !bearer_token.is_empty() {
+        let authorized = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| secret_matches(token.trim().as_bytes(), bearer_token));
+        if !authorized {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+
+    telemetry::update_gauges(&state.services).await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        telemetry::prometheus().render(),
+    )
+        .into_response()
+}
+
+pub fn routes() -> Router<DisplexState> {
+    Router::new().route("/metrics", get(metrics))
+}
@@ -1,24 +1,128 @@
+use std::net::SocketAddr;
+
 use axum::{
+    extract::ConnectInfo,
+    response::{
+        IntoResponse,
+        Redirect,
+        Response,
+    },
     routing::get_service,
     Router,
 };
-use http::StatusCode;
+use http::{
+    header::AUTHORIZATION,
+    HeaderMap,
+    StatusCode,
+};
+use subtle::ConstantTimeEq;
 use tower_http::services::{
     ServeDir,
     ServeFile,
 };
 
-use crate::config::AppConfig;
+use crate::config::{
+    AppConfig,
+    WebConfig,
+};
+
+use crate::errors::DisplexError;
 
 use super::DisplexState;
 
 mod discord;
 mod graphql;
+mod health;
+mod metrics;
+mod overseerr;
 mod plex;
+mod session;
+mod tautulli;
+
+/// Compare a secret supplied by a client with the configured one in constant time.
+fn secret_matches(supplied: &[u8], secret: &str) -> bool {
+    !secret.is_empty() && bool::from(supplied.ct_eq(secret.as_bytes()))
+}
+
+/// Check a webhook's `Authorization` header against its configured shared secret.
+fn is_authorized(headers: &HeaderMap, secret: &str) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .is_some_and(|value| secret_matches(value.as_bytes(), secret))
+}
+
+/// The address of the client, taken from `X-Forwarded-For` when running behind a trusted proxy.
+fn client_ip(
+    headers: &HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    trust_forwarded_for: bool,
+) -> Option<String> {
+    headers
+        .get("x-forwarded-for")
+        .filter(|_| trust_forwarded_for)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .map(String::from)
+        .or_else(|| connect_info.map(|ConnectInfo(addr)| addr.ip().to_string()))
+}
+
+/// Check the auth flow's `next` parameter is a relative path, a `discord://` link or on one of
+/// the configured origins, so we can't be used as an open redirect.
+fn is_allowed_redirect(config: &WebConfig, next: &str) -> bool {
+    if next.starts_with('/') {
+        // `//host` and `/\host` are treated as absolute URLs by browsers.
+        return !next.starts_with("//")
+            && !next.contains('\\')
+            && !next.chars().any(char::is_control);
+    }
+    let Ok(url) = reqwest::Url::parse(next) else {
+        return false;
+    };
+    if url.scheme() == "discord" {
+        return true;
+    }
+    let origin = url.origin().ascii_serialization();
+    config
+        .allowed_redirect_origins
+        .iter()
+        .filter_map(|allowed| reqwest::Url::parse(allowed).ok())
+        .any(|allowed| allowed.origin().ascii_serialization() == origin)
+}
+
+/// Check the auth flow's `next` parameter, if it has one.
+fn check_redirect(config: &WebConfig, next: Option<&str>) -> Result<(), DisplexError> {
+    match next {
+        Some(next) if !is_allowed_redirect(config, next) => {
+            Err(DisplexError::InvalidRedirect(next.to_owned()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Redirect to `next` once the auth flow is done.
+fn redirect_next(config: &WebConfig, next: Option<&str>) -> Result<Response, DisplexError> {
+    check_redirect(config, next)?;
+    Ok(Redirect::to(next.unwrap_or("/")).into_response())
+}
 
 pub fn configure(config: &AppConfig) -> Router<DisplexState> {
-    let mut router = Router::new().merge(discord::routes()).merge(plex::routes());
+    let mut router = Router::new()
+        .merge(discord::routes())
+        .merge(health::routes())
+        .merge(plex::routes())
+        .merge(session::routes());
 
+    if !config.overseerr.webhook.secret.is_empty() {
+        router = router.merge(overseerr::routes());
+    }
+    if !config.tautulli.webhook.secret.is_empty() {
+        router = router.merge(tautulli::routes());
+    }
+    if config.metrics.enabled {
+        router = router.merge(metrics::routes());
+    }
     if config.api.enabled {
         router = router.nest("/gql", graphql::routes());
     }
@@ -38,3 +142,75 @@ pub fn configure(config: &AppConfig) -> Router<DisplexState> {
             }),
         )
 }
+
+#[cfg(test)]
+mod test {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn is_authorized_works() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_authorized(&headers, "secret"));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("secret"));
+        assert!(is_authorized(&headers, "secret"));
+        assert!(!is_authorized(&headers, "other"));
+        assert!(!is_authorized(&headers, ""));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static(""));
+        assert!(!is_authorized(&headers, ""));
+    }
+
+    #[test]
+    fn client_ip_works() {
+        let connect_info = Some(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 1234))));
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers, None, true), None);
+        assert_eq!(
+            client_ip(&headers, connect_info, true),
+            Some(String::from("10.0.0.1"))
+        );
+
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.2"),
+        );
+        assert_eq!(
+            client_ip(&headers, connect_info, true),
+            Some(String::from("203.0.113.7"))
+        );
+        assert_eq!(
+            client_ip(&headers, connect_info, false),
+            Some(String::from("10.0.0.1"))
+        );
+    }
+
+    #[test]
+    fn is_allowed_redirect_works() {
+        let config = WebConfig {
+            allowed_redirect_origins: vec![String::from("https://example.com")],
+            ..Default::default()
+        };
+        assert!(is_allowed_redirect(&config, "/"));
+        assert!(is_allowed_redirect(
+            &config,
+            "/auth/plex?next=discord://-/channels/1/@home"
+        ));
+        assert!(is_allowed_redirect(&config, "discord://-/channels/1/@home"));
+        assert!(is_allowed_redirect(&config, "https://example.com/page"));
+        assert!(is_allowed_redirect(&config, "https://EXAMPLE.com:443/"));
+
+        assert!(!is_allowed_redirect(&config, "//evil.com"));
+        assert!(!is_allowed_redirect(&config, "/\\evil.com"));
+        assert!(!is_allowed_redirect(&config, "https://evil.com"));
+        assert!(!is_allowed_redirect(
+            &config,
+            "https://example.com.evil.com"
+        ));
+        assert!(!is_allowed_redirect(&config, "http://example.com"));
+        assert!(!is_allowed_redirect(&config, "javascript:alert(1)"));
+        assert!(!is_allowed_redirect(&config, "evil.com"));
+    }
+}
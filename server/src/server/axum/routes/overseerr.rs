@@ -0,0 +1,135 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    routing::post,
+    Router,
+};
+use http::{
+    HeaderMap,
+    StatusCode,
+};
+
+use crate::{
+    bot::discord::notifications,
+    errors::DisplexError,
+    server::axum::DisplexState,
+    services::overseerr::models::{
+        WebhookNotificationType,
+        WebhookPayload,
+        WebhookRequest,
+    },
+};
+
+use super::is_authorized;
+
+async fn webhook(
+    State(state): State<DisplexState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, DisplexError> {
+    let webhook_config = &state.config.overseerr.webhook;
+    if !is_authorized(&headers, &webhook_config.secret) {
+        return Ok(StatusCode::UNAUTHORIZED);
+    }
+    let payload: WebhookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(err) => {
+            tracing::warn!("invalid Overseerr webhook payload: {err}");
+            return Ok(StatusCode::BAD_REQUEST);
+        }
+    };
+    tracing::info!(
+        "received Overseerr webhook {:?}: {}",
+        payload.notification_type,
+        payload.subject
+    );
+    if payload.notification_type == WebhookNotificationType::TestNotification {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    let discord_user_id = match &payload.request {
+        Some(request) => resolve_discord_user(&state, request).await,
+        None => None,
+    };
+    let discord_svc = &state.services.discord_service;
+
+    if let (true, Some(user_id)) = (webhook_config.direct_message, discord_user_id) {
+        if let Some(message) = notifications::request_status(&payload, None) {
+            if let Err(err) = discord_svc.send_direct_message(user_id, message).await {
+                tracing::warn!("unable to DM {user_id} about their request: {err:?}");
+            }
+        }
+    }
+    if let Some(channel_id) = webhook_config.channel_id {
+        if let Some(message) = notifications::request_status(&payload, discord_user_id) {
+            if let Err(err) = discord_svc.send_message(channel_id, message).await {
+                tracing::warn!("unable to post request update to {channel_id}: {err:?}");
+            }
+        }
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Find the Discord user who made the request through the Plex account Overseerr has for them,
+/// falling back to the Discord id set in their Overseerr notification settings.
+///
+/// `requestedBy_username` is the Overseerr display name, which users can change, so it's only
+/// matched against Plex usernames when neither of those is available.
+async fn resolve_discord_user(state: &DisplexState, request: &WebhookRequest) -> Option<u64> {
+    let services = &state.services;
+    match services
+        .overseerr_service
+        .get_request(&request.request_id)
+        .await
+    {
+        Ok(media_request) => {
+            if let Some(requested_by) = media_request.requested_by {
+                match services
+                    .overseerr_service
+                    .linked_plex_user(&requested_by)
+                    .await
+                {
+                    Ok(Some(plex_user)) => return plex_user.discord_user_id.parse().ok(),
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!(
+                        "unable to look up Plex user {}: {:?}",
+                        requested_by.plex_id,
+                        err
+                    ),
+                }
+            }
+        }
+        Err(err) => tracing::warn!(
+            "unable to look up Overseerr request {}: {:?}",
+            request.request_id,
+            err
+        ),
+    }
+    if let Some(discord_user_id) = request
+        .requested_by_discord_id
+        .as_ref()
+        .and_then(|id| id.parse().ok())
+    {
+        return Some(discord_user_id);
+    }
+    match services
+        .plex_users_service
+        .get_by_username(&request.requested_by_username)
+        .await
+    {
+        Ok(Some(plex_user)) => plex_user.discord_user_id.parse().ok(),
+        Ok(None) => None,
+        Err(err) => {
+            tracing::warn!(
+                "unable to look up Plex user {}: {:?}",
+                request.requested_by_username,
+                err
+            );
+            None
+        }
+    }
+}
+
+pub fn routes() -> Router<DisplexState> {
+    Router::new().route("/hooks/overseerr", post(webhook))
+}
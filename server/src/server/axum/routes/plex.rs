@@ -7,6 +7,7 @@ use axum::{
     response::{
         IntoResponse,
         Redirect,
+        Response,
     },
     routing::get,
     Router,
@@ -15,20 +16,29 @@ use sea_orm::TransactionTrait;
 use serde::Deserialize;
 use tower_cookies::Cookies;
 
+use super::{
+    check_redirect,
+    redirect_next,
+};
 use crate::{
+    bot::discord::notifications,
     errors::DisplexError,
     server::{
         axum::DisplexState,
         cookies::{
-            get_cookie_data,
-            set_cookie_data,
+            get_session_data,
+            CookieData,
         },
     },
     services::{
-        discord::models::{
-            ApplicationMetadata,
-            ApplicationMetadataUpdate,
+        discord::{
+            models::{
+                ApplicationMetadata,
+                ApplicationMetadataUpdate,
+            },
+            DiscordService,
         },
+        link_request::LinkRequestsService,
         plex_token::resolver::{
             CreatePlexTokenErrorVariant,
             CreatePlexTokenResult,
@@ -49,8 +59,14 @@ struct PlexAuthQueryParams {
 async fn plex_auth(
     State(state): State<DisplexState>,
     query_string: Query<PlexAuthQueryParams>,
-) -> Result<impl IntoResponse, DisplexError> {
-    let pin = state.services.plex_service.get_pin().await?;
+) -> Result<Response, DisplexError> {
+    check_redirect(&state.config.web, query_string.next.as_deref())?;
+    let pin = state
+        .services
+        .plex_service
+        .get_pin()
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
     let next = match &query_string.next {
         Some(next) => next.to_string(),
         None => String::new(),
@@ -60,8 +76,9 @@ async fn plex_auth(
         .services
         .plex_service
         .generate_auth_url(pin.id, &pin.code, &next)
-        .await?;
-    Ok(Redirect::to(&url))
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
+    Ok(Redirect::to(&url).into_response())
 }
 
 #[derive(Deserialize)]
@@ -75,7 +92,69 @@ async fn callback(
     cookies: Cookies,
     State(state): State<DisplexState>,
     query_string: Query<CallbackQueryParams>,
-) -> Result<impl IntoResponse, DisplexError> {
+) -> Result<Response, DisplexError> {
+    // Checked before linking so a bad link doesn't leave the user half way through the flow.
+    check_redirect(&state.config.web, query_string.next.as_deref())?;
+    let cookie_data = get_session_data(&state.services, &cookies).await;
+    let discord_user_id = cookie_data.discord_user.clone();
+    let link_requests_svc = state.services.link_requests_service.clone();
+    let discord_svc = state.services.discord_service.clone();
+    let web_config = state.config.web.clone();
+
+    let result = link_plex_account(&cookie_data, state, &query_string).await;
+    if let Some(discord_user_id) = discord_user_id {
+        update_link_request(
+            &link_requests_svc,
+            &discord_svc,
+            &discord_user_id,
+            result
+                .as_ref()
+                .ok()
+                .map(|(username, is_subscribed)| (username.as_str(), *is_subscribed)),
+        )
+        .await;
+    }
+    let (plex_username, is_subscribed) = result?;
+    if !is_subscribed {
+        return Err(DisplexError::NotSubscribed(plex_username));
+    }
+
+    redirect_next(&web_config, query_string.next.as_deref())
+}
+
+/// Edit the reply to the user's `/link` command, if they started the flow from Discord.
+async fn update_link_request(
+    link_requests_svc: &LinkRequestsService,
+    discord_svc: &DiscordService,
+    discord_user_id: &str,
+    result: Option<(&str, bool)>,
+) {
+    let link_request = match link_requests_svc.take(discord_user_id).await {
+        Ok(Some(link_request)) => link_request,
+        Ok(None) => return,
+        Err(err) => {
+            tracing::warn!("unable to fetch link request for {discord_user_id}: {err:?}");
+            return;
+        }
+    };
+    if let Err(err) = discord_svc
+        .edit_interaction_response(
+            &link_request.interaction_token,
+            &notifications::link_completed(result),
+        )
+        .await
+    {
+        tracing::warn!("unable to update link request for {discord_user_id}: {err:?}");
+    }
+}
+
+/// Link the Plex account to the signed in Discord user, returning the Plex username and whether
+/// they are a subscriber.
+async fn link_plex_account(
+    cookie_data: &CookieData,
+    state: DisplexState,
+    query_string: &CallbackQueryParams,
+) -> Result<(String, bool), DisplexError> {
     let plex_svc = state.services.plex_service;
     let tautulli_svc = state.services.tautulli_service;
     let discord_svc = state.services.discord_service;
@@ -85,47 +164,56 @@ async fn callback(
     let plex_tokens_svc = state.services.plex_tokens_service;
     let overseerr_svc = state.services.overseerr_service;
 
-    let resp = plex_svc
-        .pin_claim(query_string.id, &query_string.code)
-        .await?;
+    let sessions_svc = state.services.sessions_service;
 
-    let mut cookie_data = get_cookie_data(&state.config.session.secret_key, &cookies)?;
+    let (Some(session_id), Some(discord_user_id)) =
+        (&cookie_data.session_id, cookie_data.discord_user.clone())
+    else {
+        return Err(DisplexError::Unauthenticated);
+    };
+    // Without a Discord token we can't grant the linked role, so they need to sign in again.
     let discord_token = discord_tokens_svc
-        .latest_token(&cookie_data.discord_user.clone().unwrap())
+        .latest_token(&discord_user_id)
         .await?
-        .unwrap();
-    let plex_user = plex_svc.user(&resp.auth_token).await?;
+        .ok_or(DisplexError::Unauthenticated)?;
+
+    let resp = plex_svc
+        .pin_claim(query_string.id, &query_string.code)
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
+    let plex_user = plex_svc
+        .user(&resp.auth_token)
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
     let is_subscribed = plex_svc
         .get_devices(&resp.auth_token)
-        .await?
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?
         .iter()
         .any(|d| d.client_identifier == state.config.plex.server_id);
 
-    tracing::info!(
-        "{:?} is a subscriber: {}",
-        cookie_data.discord_user,
-        is_subscribed
-    );
+    tracing::info!("{discord_user_id} is a subscriber: {is_subscribed}");
 
+    let plex_username = plex_user.username.clone();
     state
         .services
         .db
         .transaction::<_, (), DisplexError>(|txn| {
-            let discord_user_id = cookie_data.discord_user.clone();
+            let discord_user_id = discord_user_id.clone();
             Box::pin(async move {
                 let result = plex_users_svc
                     .create_with_conn(
                         &plex_user.id.to_string(),
                         &plex_user.username,
                         is_subscribed,
-                        &discord_user_id.unwrap(),
+                        &discord_user_id,
                         txn,
                     )
                     .await?;
                 match result {
                     CreatePlexUserResult::Error(err) => match err.error {
                         CreatePlexUserErrorVariant::InternalError => {
-                            Err(DisplexError(anyhow!("internal error")))
+                            Err(DisplexError::Internal(anyhow!("internal error")))
                         }
                     },
                     _ => Ok(()),
@@ -137,7 +225,7 @@ async fn callback(
                 match result {
                     CreatePlexTokenResult::Error(err) => match err.error {
                         CreatePlexTokenErrorVariant::InternalError => {
-                            Err(DisplexError(anyhow!("internal error")))
+                            Err(DisplexError::Internal(anyhow!("internal error")))
                         }
                     },
                     _ => Ok(()),
@@ -145,8 +233,7 @@ async fn callback(
                 Ok(())
             })
         })
-        .await
-        .expect("failed to save plex user info to database");
+        .await?;
 
     let mut data = ApplicationMetadataUpdate {
         platform_name: String::from(&state.config.application_name),
@@ -163,15 +250,17 @@ async fn callback(
                 Some(true),
                 Some(QueryDays::Total),
             )
-            .await?;
+            .await
+            .map_err(|err| DisplexError::UpstreamUnavailable(err.into()))?;
 
         if let Some(latest) = watch_stats.first() {
             data.metadata.watched_hours = latest.total_time / 3600;
         };
     };
 
-    cookie_data.plex_user = Some(plex_user.id.to_string());
-    set_cookie_data(&state.config.session.secret_key, &cookies, &cookie_data)?;
+    sessions_svc
+        .set_plex_user(session_id, &plex_user.id.to_string())
+        .await?;
 
     discord_svc
         .link_application(
@@ -179,20 +268,14 @@ async fn callback(
             data,
             &discord_token.access_token,
         )
-        .await?;
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
     overseerr_svc
-        .verified_user(
-            &cookie_data.discord_user.unwrap(),
-            &plex_user.id.to_string(),
-        )
-        .await?;
+        .verified_user(&discord_user_id, &plex_user.id.to_string())
+        .await
+        .map_err(DisplexError::UpstreamUnavailable)?;
 
-    let url = match &query_string.next {
-        Some(next) => next,
-        None => "/",
-    };
-    tracing::debug!("url:{}", url);
-    Ok(Redirect::to(url))
+    Ok((plex_username, is_subscribed))
 }
 
 pub fn routes() -> Router<DisplexState> {
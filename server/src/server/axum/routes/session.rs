@@ -0,0 +1,52 @@
+use axum::{
+    extract::{
+        Query,
+        State,
+    },
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde::Deserialize;
+use tower_cookies::Cookies;
+
+use crate::{
+    errors::DisplexError,
+    server::{
+        axum::DisplexState,
+        cookies::{
+            clear_cookie_data,
+            get_cookie_data,
+        },
+    },
+};
+
+use super::{
+    check_redirect,
+    redirect_next,
+};
+
+#[derive(Deserialize)]
+struct LogoutQueryParams {
+    pub next: Option<String>,
+}
+
+async fn logout(
+    cookies: Cookies,
+    State(state): State<DisplexState>,
+    query_string: Query<LogoutQueryParams>,
+) -> Result<Response, DisplexError> {
+    check_redirect(&state.config.web, query_string.next.as_deref())?;
+    if let Some(session_id) = get_cookie_data(&state.config.session.secret_key, &cookies)
+        .ok()
+        .and_then(|cookie_data| cookie_data.session_id)
+    {
+        state.services.sessions_service.delete(&session_id).await?;
+    }
+    clear_cookie_data(&cookies);
+    redirect_next(&state.config.web, query_string.next.as_deref())
+}
+
+pub fn routes() -> Router<DisplexState> {
+    Router::new().route("/auth/logout", post(logout))
+}
@@ -0,0 +1,89 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    routing::post,
+    Router,
+};
+use chrono::Utc;
+use http::{
+    HeaderMap,
+    StatusCode,
+};
+use serde::Deserialize;
+
+use crate::{
+    errors::DisplexError,
+    server::axum::DisplexState,
+    services::events::{
+        Event,
+        TautulliEvent,
+        TautulliEventKind,
+    },
+};
+
+use super::is_authorized;
+
+/// The JSON data configured on Tautulli's webhook agent, see the README for the template.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WebhookPayload {
+    action: String,
+    user_id: String,
+    user: String,
+    title: String,
+    media_type: String,
+}
+
+/// Tautulli leaves parameters which don't apply to a notification empty.
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|value| !value.is_empty())
+}
+
+async fn webhook(
+    State(state): State<DisplexState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, DisplexError> {
+    if !is_authorized(&headers, &state.config.tautulli.webhook.secret) {
+        return Ok(StatusCode::UNAUTHORIZED);
+    }
+    let payload: WebhookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(err) => {
+            tracing::warn!("invalid Tautulli webhook payload: {err}");
+            return Ok(StatusCode::BAD_REQUEST);
+        }
+    };
+    let Some(kind) = TautulliEventKind::from_action(&payload.action) else {
+        tracing::debug!("ignoring Tautulli webhook action {:?}", payload.action);
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    tracing::info!("received Tautulli webhook {kind:?}");
+    // Only drop the responses the event makes stale, so the rest of the cache stays warm.
+    let stale: &[&str] = match kind {
+        TautulliEventKind::Play => &["get_activity"],
+        TautulliEventKind::Stop => &["get_activity", "get_home_stats", "get_users_table"],
+        TautulliEventKind::RecentlyAdded => &["get_libraries"],
+        TautulliEventKind::ServerDown | TautulliEventKind::ServerUp => &["server_status"],
+    };
+    for cmd in stale {
+        state.services.tautulli_service.invalidate(cmd).await;
+    }
+
+    state
+        .services
+        .event_bus
+        .publish(Event::Tautulli(TautulliEvent {
+            kind,
+            user_id: non_empty(payload.user_id),
+            user: non_empty(payload.user),
+            title: non_empty(payload.title),
+            media_type: non_empty(payload.media_type),
+            received_at: Utc::now(),
+        }));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<DisplexState> {
+    Router::new().route("/hooks/tautulli", post(webhook))
+}
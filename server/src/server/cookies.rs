@@ -17,37 +17,48 @@ use serde::{
 };
 use tower_cookies::Cookies;
 
+use crate::{
+    config::SessionConfig,
+    services::AppServices,
+};
+
 pub const DISPLEX_COOKIE: &str = "displex";
 
 #[derive(Enum, Debug, Deserialize, Serialize, Default, Copy, Clone, Eq, PartialEq)]
 pub enum Role {
     Admin,
+    /// Can view everything an admin can, but not change it.
+    Moderator,
     User,
     #[default]
     Anonymous,
 }
 
+/// The session cookie only stores the OAuth2 state and the session id, who the user is and their
+/// role are loaded from the session on each request by [`get_session_data`].
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct CookieData {
     #[serde(rename = "ds")]
     pub discord_state: Option<String>,
-    #[serde(rename = "du")]
+    #[serde(rename = "sid")]
+    pub session_id: Option<String>,
+    #[serde(skip)]
     pub discord_user: Option<String>,
-    #[serde(rename = "pu")]
+    #[serde(skip)]
     pub plex_user: Option<String>,
-    #[serde(rename = "r", default)]
+    #[serde(skip)]
     pub role: Role,
 }
 
-impl From<&CookieData> for Cookie<'_> {
-    fn from(value: &CookieData) -> Self {
-        Cookie::build((DISPLEX_COOKIE, serde_json::to_string(value).unwrap()))
+impl CookieData {
+    /// The cookie holding this data, which expires along with the session after `ttl`.
+    fn to_cookie(&self, ttl: Duration) -> Cookie<'static> {
+        Cookie::build((DISPLEX_COOKIE, serde_json::to_string(self).unwrap()))
             .same_site(SameSite::Lax)
             .http_only(true)
             .secure(true)
             .path("/")
-            // 6 months
-            .expires(OffsetDateTime::now_utc() + Duration::from_secs(60 * 60 * 24 * 30 * 6))
+            .expires(OffsetDateTime::now_utc() + ttl)
             .build()
     }
 }
@@ -61,39 +72,73 @@ pub fn get_cookie_data(secret_key: &str, cookies: &Cookies) -> anyhow::Result<Co
     serde_json::from_str(cookie_data.value_trimmed()).map_err(|err| anyhow!(err))
 }
 
+impl Role {
+    /// Whether this role has at least the access of `other`.
+    pub fn includes(&self, other: Role) -> bool {
+        self.level() >= other.level()
+    }
+
+    fn level(&self) -> u8 {
+        match self {
+            Role::Admin => 3,
+            Role::Moderator => 2,
+            Role::User => 1,
+            Role::Anonymous => 0,
+        }
+    }
+}
+
+/// Read the session cookie and load the user from their session, if it is still active.
+///
+/// The role is worked out on every request, so changes to `api.admin_discord_ids` and the user's
+/// guild roles apply without them signing in again.
+pub async fn get_session_data(services: &AppServices, cookies: &Cookies) -> CookieData {
+    let mut cookie_data =
+        get_cookie_data(&services.config.session.secret_key, cookies).unwrap_or_default();
+    let Some(session_id) = &cookie_data.session_id else {
+        return cookie_data;
+    };
+    match services.sessions_service.get_active(session_id).await {
+        Ok(Some(session)) => {
+            cookie_data.role = services
+                .roles_service
+                .role_for(&session.discord_user_id)
+                .await;
+            cookie_data.discord_user = Some(session.discord_user_id);
+            cookie_data.plex_user = session.plex_user_id;
+        }
+        Ok(None) => tracing::debug!("session has expired or been revoked"),
+        Err(err) => tracing::warn!("unable to load session: {:?}", err),
+    }
+    cookie_data
+}
+
 pub fn set_cookie_data(
-    secret_key: &str,
+    session_config: &SessionConfig,
     cookies: &Cookies,
     cookie_data: &CookieData,
 ) -> anyhow::Result<()> {
-    let key = Key::from(secret_key.as_bytes());
+    let key = Key::from(session_config.secret_key.as_bytes());
     let signed = cookies.signed(&key);
-    signed.add(cookie_data.into());
+    signed.add(cookie_data.to_cookie(session_config.ttl));
     Ok(())
 }
 
+/// Remove the session cookie, signing the user out.
+pub fn clear_cookie_data(cookies: &Cookies) {
+    cookies.remove(Cookie::build(DISPLEX_COOKIE).path("/").build());
+}
+
 pub fn verify_role(ctx: &Context<'_>, expected_role: Role) -> anyhow::Result<()> {
     let role = match ctx.data::<CookieData>() {
-        Ok(cookie) => &cookie.role,
-        Err(_) => &Role::Anonymous,
+        Ok(cookie) => cookie.role,
+        Err(_) => Role::Anonymous,
     };
     tracing::info!("verifying role: {role:?} >= {expected_role:?}");
-    match expected_role {
-        Role::Admin => {
-            if [Role::Admin].contains(role) {
-                Ok(())
-            } else {
-                Err(anyhow!("unauthorized"))
-            }
-        }
-        Role::User => {
-            if [Role::Admin, Role::User].contains(role) {
-                Ok(())
-            } else {
-                Err(anyhow!("unauthorized"))
-            }
-        }
-        _ => Err(anyhow!("unauthorized")),
+    if expected_role != Role::Anonymous && role.includes(expected_role) {
+        Ok(())
+    } else {
+        Err(anyhow!("unauthorized"))
     }
 }
 
@@ -111,23 +156,56 @@ pub fn get_plex_id(ctx: &Context<'_>) -> anyhow::Result<String> {
 mod test {
     use super::*;
 
+    #[test]
+    fn role_includes_works() {
+        assert!(Role::Admin.includes(Role::Moderator));
+        assert!(Role::Moderator.includes(Role::Moderator));
+        assert!(Role::Moderator.includes(Role::User));
+        assert!(!Role::Moderator.includes(Role::Admin));
+        assert!(!Role::User.includes(Role::Moderator));
+        assert!(!Role::Anonymous.includes(Role::User));
+    }
+
     #[test]
     fn serde_works() {
-        let json = "{\"ds\":\"ds\",\"du\":\"du\",\"pu\":\"1\"}";
+        let json = "{\"ds\":\"ds\",\"sid\":\"sid\"}";
         let data: CookieData = serde_json::from_str(json).unwrap();
         assert_eq!(data.discord_state, Some(String::from("ds")));
-        assert_eq!(data.discord_user, Some(String::from("du")));
-        assert_eq!(data.plex_user, Some(String::from("1")));
+        assert_eq!(data.session_id, Some(String::from("sid")));
+        assert_eq!(data.discord_user, None);
+        assert_eq!(data.plex_user, None);
         assert_eq!(data.role, Role::Anonymous);
     }
 
     #[test]
-    fn serde_works_role() {
+    fn cookie_expires_with_session() {
+        let ttl = Duration::from_secs(60 * 60);
+        let cookie = CookieData::default().to_cookie(ttl);
+        let expires = cookie.expires_datetime().unwrap();
+        let remaining = expires - OffsetDateTime::now_utc();
+        assert!(remaining <= ttl && remaining > ttl - Duration::from_secs(60));
+    }
+
+    #[test]
+    fn serde_ignores_identity() {
+        // Cookies from before sessions were stored server side can't be revoked, so aren't trusted.
         let json = "{\"ds\":\"ds\",\"du\":\"du\",\"pu\":\"1\",\"r\":\"Admin\"}";
         let data: CookieData = serde_json::from_str(json).unwrap();
         assert_eq!(data.discord_state, Some(String::from("ds")));
-        assert_eq!(data.discord_user, Some(String::from("du")));
-        assert_eq!(data.plex_user, Some(String::from("1")));
-        assert_eq!(data.role, Role::Admin);
+        assert_eq!(data.session_id, None);
+        assert_eq!(data.discord_user, None);
+        assert_eq!(data.plex_user, None);
+        assert_eq!(data.role, Role::Anonymous);
+
+        let data = CookieData {
+            session_id: Some(String::from("sid")),
+            discord_user: Some(String::from("du")),
+            role: Role::Admin,
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&data).unwrap(),
+            "{\"ds\":null,\"sid\":\"sid\"}"
+        );
     }
 }
@@ -0,0 +1,246 @@
+use async_graphql::{
+    Context,
+    Enum,
+    InputObject,
+    Object,
+    Result,
+    SimpleObject,
+    Union,
+};
+use chrono::Utc;
+use sea_orm::{
+    prelude::*,
+    ActiveValue,
+    QueryOrder,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        api_key::{
+            self,
+            ApiKeyScope,
+        },
+        prelude::*,
+    },
+    graphql::ApiKeyScopes,
+    server::cookies::{
+        verify_role,
+        Role,
+    },
+};
+
+/// Prepended to every generated key, so they are easy to recognise.
+const KEY_PREFIX: &str = "dpx_";
+/// How much of the key is stored in plaintext to tell keys apart.
+const DISPLAYED_KEY_LENGTH: usize = 12;
+
+/// Requests made with an API key act as an admin, so they mustn't be able to manage keys
+/// themselves, otherwise a key could give itself more scopes.
+fn verify_not_api_key(gql_ctx: &Context<'_>) -> anyhow::Result<()> {
+    if gql_ctx.data_opt::<ApiKeyScopes>().is_some() {
+        anyhow::bail!("API keys can't be used to manage API keys");
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct ApiKeysQuery;
+
+#[Object]
+impl ApiKeysQuery {
+    async fn list_api_keys(&self, gql_ctx: &Context<'_>) -> Result<Vec<api_key::Model>> {
+        verify_role(gql_ctx, Role::Admin)?;
+        gql_ctx.data_unchecked::<ApiKeysService>().list().await
+    }
+}
+
+#[derive(Default)]
+pub struct ApiKeysMutation;
+
+#[Object]
+impl ApiKeysMutation {
+    /// Create an API key, the key is only ever returned here.
+    async fn create_api_key(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: CreateApiKeyInput,
+    ) -> Result<CreatedApiKey> {
+        verify_role(gql_ctx, Role::Admin)?;
+        verify_not_api_key(gql_ctx)?;
+        gql_ctx
+            .data_unchecked::<ApiKeysService>()
+            .create(&input.name, input.scopes)
+            .await
+    }
+
+    async fn revoke_api_key(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: RevokeApiKeyInput,
+    ) -> Result<RevokeApiKeyResult> {
+        verify_role(gql_ctx, Role::Admin)?;
+        verify_not_api_key(gql_ctx)?;
+        gql_ctx
+            .data_unchecked::<ApiKeysService>()
+            .revoke(input.id)
+            .await
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct CreateApiKeyInput {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct RevokeApiKeyInput {
+    pub id: i32,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct CreatedApiKey {
+    pub api_key: api_key::Model,
+    pub key: String,
+}
+
+#[derive(Enum, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum RevokeApiKeyErrorVariant {
+    ApiKeyDoesNotExist,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct RevokeApiKeyError {
+    pub error: RevokeApiKeyErrorVariant,
+}
+
+#[derive(Debug, Union)]
+pub enum RevokeApiKeyResult {
+    Ok(api_key::Model),
+    Err(RevokeApiKeyError),
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKeysService {
+    db: DatabaseConnection,
+}
+
+impl ApiKeysService {
+    pub fn new(db: &DatabaseConnection) -> Self {
+        Self { db: db.clone() }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create(&self, name: &str, scopes: Vec<ApiKeyScope>) -> Result<CreatedApiKey> {
+        let key = format!(
+            "{KEY_PREFIX}{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let data = api_key::ActiveModel {
+            name: ActiveValue::Set(name.to_owned()),
+            key_prefix: ActiveValue::Set(key[..DISPLAYED_KEY_LENGTH].to_owned()),
+            key_hash: ActiveValue::Set(hash_key(&key)),
+            scopes: ActiveValue::Set(scopes),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..Default::default()
+        };
+        let api_key = ApiKey::insert(data).exec_with_returning(&self.db).await?;
+        Ok(CreatedApiKey { api_key, key })
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn list(&self) -> Result<Vec<api_key::Model>> {
+        Ok(ApiKey::find()
+            .order_by_asc(api_key::Column::Id)
+            .all(&self.db)
+            .await?)
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn revoke(&self, id: i32) -> Result<RevokeApiKeyResult> {
+        let Some(api_key) = ApiKey::find_by_id(id).one(&self.db).await? else {
+            return Ok(RevokeApiKeyResult::Err(RevokeApiKeyError {
+                error: RevokeApiKeyErrorVariant::ApiKeyDoesNotExist,
+            }));
+        };
+        if api_key.revoked_at.is_some() {
+            return Ok(RevokeApiKeyResult::Ok(api_key));
+        }
+        Ok(RevokeApiKeyResult::Ok(
+            ApiKey::update(api_key::ActiveModel {
+                id: ActiveValue::Set(id),
+                revoked_at: ActiveValue::Set(Some(Utc::now())),
+                ..Default::default()
+            })
+            .exec(&self.db)
+            .await?,
+        ))
+    }
+
+    /// Find the unrevoked API key matching `key`, recording that it was used.
+    #[instrument(skip_all)]
+    pub async fn authenticate(&self, key: &str) -> Result<Option<api_key::Model>> {
+        let Some(api_key) = ApiKey::find()
+            .filter(api_key::Column::KeyHash.eq(hash_key(key)))
+            .filter(api_key::Column::RevokedAt.is_null())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(
+            ApiKey::update(api_key::ActiveModel {
+                id: ActiveValue::Set(api_key.id),
+                last_used_at: ActiveValue::Set(Some(Utc::now())),
+                ..Default::default()
+            })
+            .exec(&self.db)
+            .await?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_graphql::{
+        EmptySubscription,
+        Request,
+        Schema,
+    };
+
+    use super::*;
+    use crate::server::cookies::CookieData;
+
+    #[tokio::test]
+    async fn api_keys_cannot_create_api_keys() {
+        let schema = Schema::build(ApiKeysQuery, ApiKeysMutation, EmptySubscription)
+            .data(ApiKeysService::new(&DatabaseConnection::Disconnected))
+            .finish();
+        let request = Request::new(
+            "mutation { createApiKey(input: { name: \"escalate\", scopes: [READ, WRITE] }) { key } }",
+        )
+        .data(CookieData {
+            role: Role::Admin,
+            ..Default::default()
+        })
+        .data(ApiKeyScopes(vec![ApiKeyScope::Write]));
+
+        let response = schema.execute(request).await;
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(
+            response.errors[0].message,
+            "API keys can't be used to manage API keys"
+        );
+    }
+}
@@ -4,18 +4,38 @@ use reqwest::Url;
 use serenity::{
     all::{
         ChannelId,
+        CreateMessage,
+        EditInteractionResponse,
         GuildId,
+        RoleId,
+        UserId,
+    },
+    gateway::{
+        ConnectionStage,
+        ShardManager,
+    },
+    http::{
+        Http,
+        HttpError,
     },
-    http::Http,
     json::JsonMap,
     model::prelude::{
         GuildChannel,
+        Member,
         Role,
     },
 };
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    OnceLock,
+};
 use tracing::instrument;
 
+use crate::telemetry::{
+    observe,
+    InjectTraceContext,
+};
+
 use self::{
     models::{
         ApplicationMetadataUpdate,
@@ -30,11 +50,16 @@ use self::{
 pub mod models;
 pub mod oauth2;
 
+/// The most members Discord returns in one page.
+const GUILD_MEMBERS_PAGE_SIZE: u64 = 1000;
+
 #[derive(Clone, Debug)]
 pub struct DiscordService {
     client: reqwest::Client,
     oauth2_client: DiscordOAuth2Client,
     discord_http_client: Arc<Http>,
+    /// Set once the bot is started in this process.
+    shard_manager: Arc<OnceLock<Arc<ShardManager>>>,
 }
 
 impl DiscordService {
@@ -48,9 +73,29 @@ impl DiscordService {
             client: client.clone(),
             discord_http_client: Arc::new(discord_http_client),
             oauth2_client: DiscordOAuth2Client::new(client.clone(), client_id, client_secret),
+            shard_manager: Default::default(),
         }
     }
 
+    pub fn set_shard_manager(&self, shard_manager: Arc<ShardManager>) {
+        if self.shard_manager.set(shard_manager).is_err() {
+            tracing::warn!("bot shard manager already set");
+        }
+    }
+
+    /// Whether every shard of the bot is connected to the gateway, `None` when the bot isn't
+    /// running in this process.
+    pub async fn gateway_connected(&self) -> Option<bool> {
+        let shard_manager = self.shard_manager.get()?;
+        let runners = shard_manager.runners.lock().await;
+        Some(
+            !runners.is_empty()
+                && runners
+                    .values()
+                    .all(|runner| runner.stage == ConnectionStage::Connected),
+        )
+    }
+
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn link_application(
         &self,
@@ -58,27 +103,35 @@ impl DiscordService {
         metadata: ApplicationMetadataUpdate,
         token: &str,
     ) -> Result<()> {
-        self.client
-            .put(format_url(&format!(
-                "/users/@me/applications/{application_id}/role-connection"
-            )))
-            .bearer_auth(token)
-            .json(&metadata)
-            .send()
-            .await?;
-        Ok(())
+        observe("discord", "link_application", async {
+            self.client
+                .put(format_url(&format!(
+                    "/users/@me/applications/{application_id}/role-connection"
+                )))
+                .bearer_auth(token)
+                .json(&metadata)
+                .inject_trace_context()
+                .send()
+                .await?;
+            Ok(())
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn user(&self, token: &str) -> Result<User> {
-        Ok(self
-            .client
-            .get(format_url("/users/@me"))
-            .bearer_auth(token)
-            .send()
-            .await?
-            .json()
-            .await?)
+        observe("discord", "user", async {
+            Ok(self
+                .client
+                .get(format_url("/users/@me"))
+                .bearer_auth(token)
+                .inject_trace_context()
+                .send()
+                .await?
+                .json()
+                .await?)
+        })
+        .await
     }
 
     #[instrument(skip(self), ret)]
@@ -88,33 +141,100 @@ impl DiscordService {
 
     #[instrument(skip(self), ret)]
     pub async fn token(&self, code: &str, redirect_url: &str) -> Result<DiscordOAuth2Token> {
-        self.oauth2_client.token(code, redirect_url).await
+        observe("discord", "token", async {
+            self.oauth2_client.token(code, redirect_url).await
+        })
+        .await
     }
 
     #[instrument(skip(self), ret)]
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<DiscordOAuth2Token> {
-        self.oauth2_client.refresh_token(refresh_token).await
+        observe("discord", "refresh_token", async {
+            self.oauth2_client.refresh_token(refresh_token).await
+        })
+        .await
     }
 
     #[instrument(skip(self), ret)]
     pub async fn revoke_token(&self, refresh_token: &str) -> Result<()> {
-        self.oauth2_client.revoke_token(refresh_token).await
+        observe("discord", "revoke_token", async {
+            self.oauth2_client.revoke_token(refresh_token).await
+        })
+        .await
+    }
+
+    #[instrument(skip(self, interaction_token), level = "debug")]
+    pub async fn edit_interaction_response(
+        &self,
+        interaction_token: &str,
+        response: &EditInteractionResponse,
+    ) -> Result<()> {
+        observe("discord", "edit_interaction_response", async {
+            self.discord_http_client
+                .edit_original_interaction_response(interaction_token, response, vec![])
+                .await?;
+            Ok(())
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn get_guild_roles(&self, guild_id: u64) -> Result<Vec<Role>> {
-        Ok(self
-            .discord_http_client
-            .get_guild_roles(GuildId::new(guild_id))
-            .await?)
+        observe("discord", "get_guild_roles", async {
+            Ok(self
+                .discord_http_client
+                .get_guild_roles(GuildId::new(guild_id))
+                .await?)
+        })
+        .await
+    }
+
+    /// Every member of the guild, fetched a page at a time. The bot needs the Server Members
+    /// intent.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn list_guild_members(&self, guild_id: u64) -> Result<Vec<Member>> {
+        let mut members: Vec<Member> = vec![];
+        loop {
+            let after = members.last().map(|member| member.user.id.get());
+            let page = observe("discord", "get_guild_members", async {
+                self.discord_http_client
+                    .get_guild_members(GuildId::new(guild_id), Some(GUILD_MEMBERS_PAGE_SIZE), after)
+                    .await
+            })
+            .await?;
+            let done = (page.len() as u64) < GUILD_MEMBERS_PAGE_SIZE;
+            members.extend(page);
+            if done {
+                return Ok(members);
+            }
+        }
+    }
+
+    /// The ids of the roles the user has in the guild.
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn get_member_role_ids(&self, guild_id: u64, user_id: u64) -> Result<Vec<u64>> {
+        observe("discord", "get_member_role_ids", async {
+            Ok(self
+                .discord_http_client
+                .get_member(GuildId::new(guild_id), UserId::new(user_id))
+                .await?
+                .roles
+                .into_iter()
+                .map(|role_id| role_id.get())
+                .collect())
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn get_channels(&self, guild_id: u64) -> Result<Vec<GuildChannel>> {
-        Ok(self
-            .discord_http_client
-            .get_channels(GuildId::new(guild_id))
-            .await?)
+        observe("discord", "get_channels", async {
+            Ok(self
+                .discord_http_client
+                .get_channels(GuildId::new(guild_id))
+                .await?)
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
@@ -124,10 +244,13 @@ impl DiscordService {
         map: &JsonMap,
         audit_log_reason: Option<&str>,
     ) -> Result<GuildChannel> {
-        Ok(self
-            .discord_http_client
-            .create_channel(GuildId::new(guild_id), map, audit_log_reason)
-            .await?)
+        observe("discord", "create_channel", async {
+            Ok(self
+                .discord_http_client
+                .create_channel(GuildId::new(guild_id), map, audit_log_reason)
+                .await?)
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
@@ -137,13 +260,93 @@ impl DiscordService {
         map: &JsonMap,
         audit_log_reason: Option<&str>,
     ) -> Result<GuildChannel> {
-        Ok(self
-            .discord_http_client
-            .edit_channel(ChannelId::new(channel_id), map, audit_log_reason)
-            .await?)
+        observe("discord", "edit_channel", async {
+            Ok(self
+                .discord_http_client
+                .edit_channel(ChannelId::new(channel_id), map, audit_log_reason)
+                .await?)
+        })
+        .await
+    }
+
+    /// Give a member a role, the reason is shown in the server's audit log.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn add_member_role(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        role_id: u64,
+        reason: &str,
+    ) -> Result<()> {
+        observe("discord", "add_member_role", async {
+            self.discord_http_client
+                .add_member_role(
+                    GuildId::new(guild_id),
+                    UserId::new(user_id),
+                    RoleId::new(role_id),
+                    Some(reason),
+                )
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Take a role away from a member, the reason is shown in the server's audit log.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn remove_member_role(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        role_id: u64,
+        reason: &str,
+    ) -> Result<()> {
+        observe("discord", "remove_member_role", async {
+            self.discord_http_client
+                .remove_member_role(
+                    GuildId::new(guild_id),
+                    UserId::new(user_id),
+                    RoleId::new(role_id),
+                    Some(reason),
+                )
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self, message), level = "debug")]
+    pub async fn send_message(&self, channel_id: u64, message: CreateMessage) -> Result<()> {
+        observe("discord", "send_message", async {
+            ChannelId::new(channel_id)
+                .send_message(self.discord_http_client.as_ref(), message)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self, message), level = "debug")]
+    pub async fn send_direct_message(&self, user_id: u64, message: CreateMessage) -> Result<()> {
+        observe("discord", "send_direct_message", async {
+            UserId::new(user_id)
+                .direct_message(self.discord_http_client.as_ref(), message)
+                .await?;
+            Ok(())
+        })
+        .await
     }
 }
 
 fn format_url(path: &str) -> String {
     format!("https://discord.com/api/v10{path}")
 }
+
+/// Whether Discord failed the request because the user isn't a member of the guild.
+pub fn is_unknown_member(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<serenity::Error>(),
+        Some(serenity::Error::Http(HttpError::UnsuccessfulRequest(response)))
+            if response.status_code.as_u16() == 404
+    )
+}
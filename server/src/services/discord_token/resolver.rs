@@ -305,6 +305,14 @@ impl DiscordTokensService {
             .await?)
     }
 
+    #[instrument(skip(self), ret)]
+    pub async fn count(&self, status: TokenStatus) -> Result<u64> {
+        Ok(DiscordToken::find()
+            .filter(discord_token::Column::Status.eq(status))
+            .count(&self.db)
+            .await?)
+    }
+
     #[instrument(skip(self), ret)]
     pub async fn delete(&self, access_token: &str) -> Result<DeleteDiscordTokenResult> {
         Ok(
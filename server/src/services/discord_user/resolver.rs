@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use async_graphql::{
     Context,
     Enum,
@@ -13,27 +14,41 @@ use sea_orm::{
     prelude::*,
     ActiveValue,
     FromJsonQueryResult,
+    TransactionTrait,
 };
 use sea_query::OnConflict;
 use serde::{
     Deserialize,
     Serialize,
 };
+use tower_cookies::Cookies;
 use tracing::instrument;
 
 use crate::{
     entities::{
-        discord_token,
+        discord_token::{
+            self,
+            TokenStatus,
+        },
         discord_user,
+        link_request,
         plex_token,
         plex_user,
         prelude::*,
+        request_tier_assignment,
+        session,
     },
     server::cookies::{
+        clear_cookie_data,
         verify_role,
+        CookieData,
         Role,
     },
     services::{
+        discord::{
+            models::ApplicationMetadataUpdate,
+            DiscordService,
+        },
         discord_token::resolver::DiscordTokensService,
         plex_token::resolver::PlexTokensService,
         plex_user::resolver::PlexUsersService,
@@ -62,7 +77,7 @@ impl DiscordUsersQuery {
         gql_ctx: &Context<'_>,
         input: GetDiscordUserInput,
     ) -> Result<GetDiscordUserResult> {
-        verify_role(gql_ctx, Role::Admin)?;
+        verify_role(gql_ctx, Role::Moderator)?;
         gql_ctx
             .data_unchecked::<DiscordUsersService>()
             .get(&input.id)
@@ -78,7 +93,7 @@ impl DiscordUsersQuery {
         gql_ctx: &Context<'_>,
         input: UserSummaryBy,
     ) -> Result<SummaryDiscordUserResult> {
-        verify_role(gql_ctx, Role::Admin)?;
+        verify_role(gql_ctx, Role::Moderator)?;
         gql_ctx
             .data_unchecked::<DiscordUsersService>()
             .summary(&input)
@@ -111,7 +126,12 @@ impl DiscordUsersMutation {
         verify_role(gql_ctx, Role::Admin)?;
         gql_ctx
             .data_unchecked::<DiscordUsersService>()
-            .update(&input.id, input.is_active)
+            .update(
+                &input.id,
+                input.is_active,
+                input.show_on_leaderboard,
+                input.show_now_playing,
+            )
             .await
     }
 
@@ -126,6 +146,25 @@ impl DiscordUsersMutation {
             .delete(&input.id)
             .await
     }
+
+    /// Remove the signed in user's linked accounts and every piece of data stored about them.
+    async fn unlink_account(&self, gql_ctx: &Context<'_>) -> Result<DeleteDiscordUserResult> {
+        verify_role(gql_ctx, Role::User)?;
+        let discord_user_id = gql_ctx
+            .data::<CookieData>()
+            .ok()
+            .and_then(|cookie| cookie.discord_user.clone())
+            .ok_or_else(|| anyhow!("requires_authentication"))?;
+        let result = gql_ctx
+            .data_unchecked::<DiscordUsersService>()
+            .unlink(&discord_user_id)
+            .await?;
+        if let (DeleteDiscordUserResult::Ok(_), Ok(cookies)) = (&result, gql_ctx.data::<Cookies>())
+        {
+            clear_cookie_data(cookies);
+        }
+        Ok(result)
+    }
 }
 
 #[derive(Debug, InputObject)]
@@ -138,6 +177,8 @@ pub struct CreateDiscordUserInput {
 pub struct UpdateDiscordUserInput {
     pub id: String,
     pub is_active: Option<bool>,
+    pub show_on_leaderboard: Option<bool>,
+    pub show_now_playing: Option<bool>,
 }
 
 #[derive(Debug, InputObject)]
@@ -236,6 +277,8 @@ pub struct DiscordUserId {
 #[derive(Debug, Clone)]
 pub struct DiscordUsersService {
     db: DatabaseConnection,
+    application_id: u64,
+    discord_service: DiscordService,
     discord_tokens_service: DiscordTokensService,
     plex_tokens_service: PlexTokensService,
     plex_users_service: PlexUsersService,
@@ -266,12 +309,16 @@ pub enum SummaryDiscordUserResult {
 impl DiscordUsersService {
     pub fn new(
         db: &DatabaseConnection,
+        application_id: u64,
+        discord_service: &DiscordService,
         discord_tokens_service: &DiscordTokensService,
         plex_tokens_service: &PlexTokensService,
         plex_users_service: &PlexUsersService,
     ) -> Self {
         Self {
             db: db.clone(),
+            application_id,
+            discord_service: discord_service.clone(),
             discord_tokens_service: discord_tokens_service.clone(),
             plex_tokens_service: plex_tokens_service.clone(),
             plex_users_service: plex_users_service.clone(),
@@ -357,6 +404,8 @@ impl DiscordUsersService {
         &self,
         id: &str,
         is_active: Option<bool>,
+        show_on_leaderboard: Option<bool>,
+        show_now_playing: Option<bool>,
     ) -> Result<UpdateDiscordUserResult> {
         let mut user = discord_user::ActiveModel {
             id: ActiveValue::Set(id.to_owned()),
@@ -366,6 +415,12 @@ impl DiscordUsersService {
         if let Some(is_active) = is_active {
             user.is_active = ActiveValue::Set(is_active);
         }
+        if let Some(show_on_leaderboard) = show_on_leaderboard {
+            user.show_on_leaderboard = ActiveValue::Set(show_on_leaderboard);
+        }
+        if let Some(show_now_playing) = show_now_playing {
+            user.show_now_playing = ActiveValue::Set(show_now_playing);
+        }
         Ok(match DiscordUser::update(user).exec(&self.db).await {
             Ok(user) => UpdateDiscordUserResult::Ok(user),
             Err(DbErr::RecordNotUpdated) => UpdateDiscordUserResult::Err(UpdateDiscordUserError {
@@ -438,6 +493,98 @@ impl DiscordUsersService {
         })
     }
 
+    /// Revoke the user's Discord tokens, clear their linked role metadata and then delete the
+    /// Discord user along with their Plex users, sessions and every token stored for them.
+    ///
+    /// Discord being unreachable doesn't stop the data from being deleted, it is only logged.
+    #[instrument(skip(self), ret)]
+    pub async fn unlink(&self, id: &str) -> Result<DeleteDiscordUserResult> {
+        let summary = match self.summary(&UserSummaryBy::Id(id.to_owned())).await? {
+            SummaryDiscordUserResult::Ok(result) => result.summary,
+            SummaryDiscordUserResult::Err(_) => {
+                return Ok(DeleteDiscordUserResult::Err(DeleteDiscordUserError {
+                    error: DeleteDiscordUserErrorVariant::UserDoesNotExist,
+                }))
+            }
+        };
+
+        let active_tokens: Vec<&discord_token::Model> = summary
+            .discord_tokens
+            .iter()
+            .filter(|token| token.status == TokenStatus::Active)
+            .collect();
+        // Tokens are sorted by expiry, so the first active one is the most recent.
+        if let Some(token) = active_tokens.first() {
+            if let Err(err) = self
+                .discord_service
+                .link_application(
+                    self.application_id,
+                    ApplicationMetadataUpdate::default(),
+                    &token.access_token,
+                )
+                .await
+            {
+                tracing::warn!("unable to clear role connection for {id}: {:?}", err);
+            }
+        }
+        for token in active_tokens {
+            if let Err(err) = self
+                .discord_service
+                .revoke_token(&token.refresh_token)
+                .await
+            {
+                tracing::warn!("unable to revoke token for {id}: {:?}", err);
+            }
+        }
+
+        let id = id.to_owned();
+        let plex_user_ids: Vec<String> = summary.plex_users.into_iter().map(|u| u.id).collect();
+        let result = self
+            .db
+            .transaction::<_, (), DbErr>(|txn| {
+                Box::pin(async move {
+                    PlexToken::delete_many()
+                        .filter(plex_token::Column::PlexUserId.is_in(plex_user_ids.clone()))
+                        .exec(txn)
+                        .await?;
+                    RequestTierAssignment::delete_many()
+                        .filter(request_tier_assignment::Column::PlexUserId.is_in(plex_user_ids))
+                        .exec(txn)
+                        .await?;
+                    PlexUser::delete_many()
+                        .filter(plex_user::Column::DiscordUserId.eq(&id))
+                        .exec(txn)
+                        .await?;
+                    DiscordToken::delete_many()
+                        .filter(discord_token::Column::DiscordUserId.eq(&id))
+                        .exec(txn)
+                        .await?;
+                    LinkRequest::delete_many()
+                        .filter(link_request::Column::DiscordUserId.eq(&id))
+                        .exec(txn)
+                        .await?;
+                    Session::delete_many()
+                        .filter(session::Column::DiscordUserId.eq(&id))
+                        .exec(txn)
+                        .await?;
+                    DiscordUser::delete_by_id(&id).exec(txn).await?;
+                    Ok(())
+                })
+            })
+            .await;
+        Ok(match result {
+            Ok(()) => DeleteDiscordUserResult::Ok(DeleteDiscordUserSuccess {
+                message: "ok".into(),
+            }),
+            Err(err) => {
+                tracing::warn!("unlink db error: {:?}", err);
+                DeleteDiscordUserResult::Err(DeleteDiscordUserError {
+                    error: DeleteDiscordUserErrorVariant::InternalError,
+                })
+            }
+        })
+    }
+
     #[instrument(skip(self), ret)]
     pub async fn summary(&self, user_by: &UserSummaryBy) -> Result<SummaryDiscordUserResult> {
         let discord_user = match user_by {
@@ -503,6 +650,31 @@ impl DiscordUsersService {
             .await?)
     }
 
+    /// Linked users who haven't opted out of appearing on the leaderboard.
+    #[instrument(skip(self))]
+    pub async fn list_leaderboard_users(
+        &self,
+    ) -> Result<Vec<(discord_user::Model, Option<plex_user::Model>)>> {
+        Ok(DiscordUser::find()
+            .find_also_related(plex_user::Entity)
+            .filter(discord_user::Column::ShowOnLeaderboard.eq(true))
+            .filter(plex_user::Column::Id.is_not_null())
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Users which have linked a Plex account.
+    #[instrument(skip(self))]
+    pub async fn list_linked_users(
+        &self,
+    ) -> Result<Vec<(discord_user::Model, Option<plex_user::Model>)>> {
+        Ok(DiscordUser::find()
+            .find_also_related(plex_user::Entity)
+            .filter(plex_user::Column::Id.is_not_null())
+            .all(&self.db)
+            .await?)
+    }
+
     pub async fn list_subscriber_tokens(
         &self,
     ) -> Result<Vec<(discord_user::Model, Option<discord_token::Model>)>> {
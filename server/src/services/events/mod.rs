@@ -0,0 +1,108 @@
+use async_graphql::{
+    Enum,
+    SimpleObject,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::sync::broadcast::{
+    self,
+    Receiver,
+    Sender,
+};
+
+/// How many events a slow consumer can fall behind by before it starts missing them.
+const CAPACITY: usize = 256;
+
+/// Something which happened outside of displex, e.g. a webhook from Tautulli.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Tautulli(TautulliEvent),
+}
+
+#[derive(Debug, Enum, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TautulliEventKind {
+    Play,
+    Stop,
+    RecentlyAdded,
+    ServerDown,
+    ServerUp,
+}
+
+impl TautulliEventKind {
+    /// Map one of Tautulli's `{action}` notification parameters, returning `None` for actions
+    /// which aren't handled.
+    pub fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "play" => Some(TautulliEventKind::Play),
+            "stop" => Some(TautulliEventKind::Stop),
+            "created" => Some(TautulliEventKind::RecentlyAdded),
+            "intdown" | "extdown" => Some(TautulliEventKind::ServerDown),
+            "intup" | "extup" => Some(TautulliEventKind::ServerUp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, SimpleObject)]
+pub struct TautulliEvent {
+    pub kind: TautulliEventKind,
+    pub user_id: Option<String>,
+    pub user: Option<String>,
+    pub title: Option<String>,
+    pub media_type: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Broadcasts events to every consumer running in this process.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: Event) {
+        // An error only means nothing is listening right now, which is fine.
+        if self.sender.send(event).is_err() {
+            tracing::debug!("no event consumers are running");
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_action_works() {
+        assert_eq!(
+            TautulliEventKind::from_action("created"),
+            Some(TautulliEventKind::RecentlyAdded)
+        );
+        assert_eq!(
+            TautulliEventKind::from_action("extdown"),
+            Some(TautulliEventKind::ServerDown)
+        );
+        assert_eq!(TautulliEventKind::from_action("pause"), None);
+    }
+}
@@ -0,0 +1,164 @@
+use async_graphql::{
+    Context,
+    InputObject,
+    Object,
+    Result,
+};
+
+use chrono::Utc;
+use sea_orm::{
+    prelude::*,
+    ActiveValue,
+    QueryOrder,
+    QuerySelect,
+    QueryTrait,
+};
+use tracing::instrument;
+
+use crate::{
+    entities::{
+        job_run::{
+            self,
+            JobRunItemError,
+            JobRunStatus,
+        },
+        prelude::*,
+    },
+    server::cookies::{
+        verify_role,
+        Role,
+    },
+};
+
+const DEFAULT_LIST_LIMIT: u64 = 50;
+
+#[derive(Default)]
+pub struct JobRunsQuery;
+
+#[Object]
+impl JobRunsQuery {
+    async fn list_job_runs(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ListJobRunsInput,
+    ) -> Result<Vec<job_run::Model>> {
+        verify_role(gql_ctx, Role::Moderator)?;
+        gql_ctx
+            .data_unchecked::<JobRunsService>()
+            .list(
+                input.job,
+                input.status,
+                input.limit.unwrap_or(DEFAULT_LIST_LIMIT),
+            )
+            .await
+    }
+
+    async fn latest_job_run(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: LatestJobRunInput,
+    ) -> Result<Option<job_run::Model>> {
+        verify_role(gql_ctx, Role::Moderator)?;
+        gql_ctx
+            .data_unchecked::<JobRunsService>()
+            .latest(&input.job, input.status)
+            .await
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct ListJobRunsInput {
+    pub job: Option<String>,
+    pub status: Option<JobRunStatus>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct LatestJobRunInput {
+    pub job: String,
+    pub status: Option<JobRunStatus>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobRunsService {
+    db: DatabaseConnection,
+}
+
+impl JobRunsService {
+    pub fn new(db: &DatabaseConnection) -> Self {
+        Self { db: db.clone() }
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn start(&self, job: &str) -> Result<job_run::Model> {
+        let data = job_run::ActiveModel {
+            job: ActiveValue::Set(job.to_owned()),
+            status: ActiveValue::Set(JobRunStatus::Running),
+            started_at: ActiveValue::Set(Utc::now()),
+            items_processed: ActiveValue::Set(0),
+            items_failed: ActiveValue::Set(0),
+            item_errors: ActiveValue::Set(vec![]),
+            ..Default::default()
+        };
+        Ok(JobRun::insert(data).exec_with_returning(&self.db).await?)
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn finish(
+        &self,
+        id: i32,
+        status: JobRunStatus,
+        items_processed: i32,
+        error: Option<String>,
+        item_errors: Vec<JobRunItemError>,
+    ) -> Result<job_run::Model> {
+        Ok(JobRun::update(job_run::ActiveModel {
+            id: ActiveValue::Set(id),
+            status: ActiveValue::Set(status),
+            finished_at: ActiveValue::Set(Some(Utc::now())),
+            items_processed: ActiveValue::Set(items_processed),
+            items_failed: ActiveValue::Set(item_errors.len() as i32),
+            error: ActiveValue::Set(error),
+            item_errors: ActiveValue::Set(item_errors),
+            ..Default::default()
+        })
+        .exec(&self.db)
+        .await?)
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn list(
+        &self,
+        job: Option<String>,
+        status: Option<JobRunStatus>,
+        limit: u64,
+    ) -> Result<Vec<job_run::Model>> {
+        Ok(JobRun::find()
+            .apply_if(job, |query, value| {
+                query.filter(job_run::Column::Job.eq(value))
+            })
+            .apply_if(status, |query, value| {
+                query.filter(job_run::Column::Status.eq(value))
+            })
+            .order_by_desc(job_run::Column::StartedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?)
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn latest(
+        &self,
+        job: &str,
+        status: Option<JobRunStatus>,
+    ) -> Result<Option<job_run::Model>> {
+        Ok(JobRun::find()
+            .filter(job_run::Column::Job.eq(job))
+            .apply_if(status, |query, value| {
+                query.filter(job_run::Column::Status.eq(value))
+            })
+            .order_by_desc(job_run::Column::StartedAt)
+            .one(&self.db)
+            .await?)
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use chrono::{
+    Duration,
+    Utc,
+};
+use sea_orm::{
+    prelude::*,
+    ActiveValue,
+};
+use sea_query::OnConflict;
+use tracing::instrument;
+
+use crate::entities::{
+    link_request,
+    prelude::*,
+};
+
+/// How long Discord allows an interaction response to be edited after the command was used.
+const INTERACTION_TOKEN_TTL_MINUTES: i64 = 15;
+
+#[derive(Debug, Clone)]
+pub struct LinkRequestsService {
+    db: DatabaseConnection,
+}
+
+impl LinkRequestsService {
+    pub fn new(db: &DatabaseConnection) -> Self {
+        Self { db: db.clone() }
+    }
+
+    #[instrument(skip(self, interaction_token))]
+    pub async fn create(&self, discord_user_id: &str, interaction_token: &str) -> Result<()> {
+        let now = Utc::now();
+        let data = link_request::ActiveModel {
+            discord_user_id: ActiveValue::Set(discord_user_id.to_owned()),
+            interaction_token: ActiveValue::Set(interaction_token.to_owned()),
+            created_at: ActiveValue::Set(now),
+            expires_at: ActiveValue::Set(now + Duration::minutes(INTERACTION_TOKEN_TTL_MINUTES)),
+        };
+        LinkRequest::insert(data)
+            .on_conflict(
+                OnConflict::column(link_request::Column::DiscordUserId)
+                    .update_columns([
+                        link_request::Column::InteractionToken,
+                        link_request::Column::CreatedAt,
+                        link_request::Column::ExpiresAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove and return the pending request for a user, if it can still be responded to.
+    #[instrument(skip(self))]
+    pub async fn take(&self, discord_user_id: &str) -> Result<Option<link_request::Model>> {
+        let link_request = LinkRequest::find_by_id(discord_user_id)
+            .one(&self.db)
+            .await?;
+        if link_request.is_some() {
+            LinkRequest::delete_by_id(discord_user_id)
+                .exec(&self.db)
+                .await?;
+        }
+        Ok(link_request.filter(|r| r.expires_at > Utc::now()))
+    }
+}
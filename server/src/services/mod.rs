@@ -1,5 +1,8 @@
+use std::num::NonZeroU64;
+
 use sea_orm::DatabaseConnection;
 use serenity::{
+    all::ApplicationId,
     http::HttpBuilder,
     prelude::TypeMapKey,
 };
@@ -10,36 +13,58 @@ use crate::{
 };
 
 use self::{
+    api_key::resolver::ApiKeysService,
     discord::DiscordService,
     discord_token::resolver::DiscordTokensService,
     discord_user::resolver::DiscordUsersService,
+    events::EventBus,
+    job_run::resolver::JobRunsService,
+    link_request::LinkRequestsService,
     overseerr::OverseerrService,
     plex::PlexService,
     plex_token::resolver::PlexTokensService,
     plex_user::resolver::PlexUsersService,
+    request_tier_assignment::resolver::RequestTierAssignmentsService,
+    roles::RolesService,
+    session::resolver::SessionsService,
     tautulli::TautulliService,
 };
 
+pub mod api_key;
 pub mod discord;
 pub mod discord_token;
 pub mod discord_user;
+pub mod events;
+pub mod job_run;
+pub mod link_request;
 pub mod overseerr;
+pub mod pagination;
 pub mod plex;
 pub mod plex_token;
 pub mod plex_user;
+pub mod request_tier_assignment;
+pub mod roles;
+pub mod session;
 pub mod tautulli;
 
 /// All the services that are used by the app
 #[derive(Clone)]
 pub struct AppServices {
+    pub api_keys_service: ApiKeysService,
     pub discord_users_service: DiscordUsersService,
     pub discord_tokens_service: DiscordTokensService,
     pub plex_users_service: PlexUsersService,
     pub plex_tokens_service: PlexTokensService,
+    pub request_tier_assignments_service: RequestTierAssignmentsService,
+    pub job_runs_service: JobRunsService,
+    pub link_requests_service: LinkRequestsService,
     pub tautulli_service: TautulliService,
     pub discord_service: DiscordService,
     pub plex_service: PlexService,
     pub overseerr_service: OverseerrService,
+    pub roles_service: RolesService,
+    pub sessions_service: SessionsService,
+    pub event_bus: EventBus,
     pub db: DatabaseConnection,
     pub reqwest_client: reqwest::Client,
     pub config: AppConfig,
@@ -64,24 +89,28 @@ pub async fn create_app_services(
         .build()
         .unwrap();
 
+    let api_keys_service = ApiKeysService::new(&db);
     let discord_tokens_service = DiscordTokensService::new(&db);
     let plex_users_service = PlexUsersService::new(&db);
     let plex_tokens_service = PlexTokensService::new(&db);
-    let discord_users_service = DiscordUsersService::new(
-        &db,
-        &discord_tokens_service,
-        &plex_tokens_service,
-        &plex_users_service,
-    );
+    let request_tier_assignments_service = RequestTierAssignmentsService::new(&db);
+    let job_runs_service = JobRunsService::new(&db);
+    let link_requests_service = LinkRequestsService::new(&db);
+    let sessions_service = SessionsService::new(&db);
     let tautulli_service = TautulliService::new(
         &reqwest_client,
         &config.tautulli.url,
         &config.tautulli.api_key,
+        &config.tautulli.cache,
     );
 
-    let http_client = HttpBuilder::new(&config.discord_bot.token)
-        .client(reqwest_client.clone())
-        .build();
+    let mut http_builder =
+        HttpBuilder::new(&config.discord_bot.token).client(reqwest_client.clone());
+    // Needed to edit interaction responses outside of the bot, e.g. once `/link` completes.
+    if let Some(application_id) = NonZeroU64::new(config.discord.client_id) {
+        http_builder = http_builder.application_id(ApplicationId::from(application_id));
+    }
+    let http_client = http_builder.build();
 
     let discord_service = DiscordService::new(
         &reqwest_client,
@@ -89,6 +118,15 @@ pub async fn create_app_services(
         config.discord.client_id,
         &config.discord.client_secret,
     );
+    let roles_service = RolesService::new(config, &discord_service);
+    let discord_users_service = DiscordUsersService::new(
+        &db,
+        config.discord.client_id,
+        &discord_service,
+        &discord_tokens_service,
+        &plex_tokens_service,
+        &plex_users_service,
+    );
     let plex_service = PlexService::new(
         &reqwest_client,
         &config.application_name,
@@ -97,20 +135,28 @@ pub async fn create_app_services(
     let overseerr_service = OverseerrService::new(
         config,
         &reqwest_client,
-        &config.overseerr.url,
-        &config.overseerr.api_key,
         &tautulli_service,
+        &discord_service,
+        &plex_users_service,
+        &request_tier_assignments_service,
     );
 
     let services = AppServices {
+        api_keys_service,
         discord_users_service,
         discord_tokens_service,
         plex_users_service,
         plex_tokens_service,
+        request_tier_assignments_service,
+        job_runs_service,
+        link_requests_service,
         tautulli_service,
         discord_service,
         plex_service,
         overseerr_service,
+        roles_service,
+        sessions_service,
+        event_bus: EventBus::new(),
         db,
         reqwest_client,
         config: config.clone(),
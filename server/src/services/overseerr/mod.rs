@@ -1,25 +1,67 @@
 pub mod models;
+pub mod notifications;
+
+use std::pin::pin;
 
 use anyhow::Result;
+use async_graphql::futures_util::{
+    Stream,
+    TryStreamExt,
+};
+use chrono::Utc;
 use tracing::{
     info,
     instrument,
 };
 
 use crate::{
-    config::AppConfig,
-    services::tautulli::{
-        models::QueryDays,
-        TautulliService,
+    config::{
+        AppConfig,
+        RequestLimitTier,
+        TierStanding,
+        WatchHoursKey,
+        WatchMedia,
+    },
+    entities::plex_user,
+    services::{
+        discord::{
+            is_unknown_member,
+            DiscordService,
+        },
+        pagination::paginate,
+        plex_user::resolver::{
+            GetPlexUserResult,
+            GetPlexUserVariant,
+            PlexUsersService,
+        },
+        request_tier_assignment::resolver::RequestTierAssignmentsService,
+        tautulli::{
+            models::{
+                MediaType as TautulliMediaType,
+                QueryDays,
+            },
+            TautulliService,
+        },
+    },
+    telemetry::{
+        observe,
+        InjectTraceContext,
     },
 };
 
 use self::models::{
     ApiResponse,
+    CreateRequest,
+    ErrorResponse,
+    Media,
+    MediaRequest,
+    MediaType,
     User,
     UserRequestSettings,
 };
 
+const PAGE_SIZE: usize = 100;
+
 #[derive(Clone, Debug)]
 pub struct OverseerrService {
     client: reqwest::Client,
@@ -27,125 +69,436 @@ pub struct OverseerrService {
     api_key: String,
     config: AppConfig,
     tautulli_service: TautulliService,
+    discord_service: DiscordService,
+    plex_users_service: PlexUsersService,
+    request_tier_assignments_service: RequestTierAssignmentsService,
 }
 
 impl OverseerrService {
     pub fn new(
         config: &AppConfig,
         client: &reqwest::Client,
-        url: &str,
-        api_key: &str,
         tautulli_service: &TautulliService,
+        discord_service: &DiscordService,
+        plex_users_service: &PlexUsersService,
+        request_tier_assignments_service: &RequestTierAssignmentsService,
     ) -> OverseerrService {
         OverseerrService {
             client: client.clone(),
-            url: String::from(url),
-            api_key: String::from(api_key),
+            url: config.overseerr.url.clone(),
+            api_key: config.overseerr.api_key.clone(),
             config: config.clone(),
             tautulli_service: tautulli_service.clone(),
+            discord_service: discord_service.clone(),
+            plex_users_service: plex_users_service.clone(),
+            request_tier_assignments_service: request_tier_assignments_service.clone(),
         }
     }
 
+    /// Check Overseerr is reachable.
     #[instrument(skip(self), ret, level = "debug")]
-    pub async fn get_users(&self) -> Result<Vec<User>> {
-        let result: ApiResponse<User> = self
-            .client
-            .get(format!("{}/api/v1/user", self.url))
-            .header("X-Api-Key", &self.api_key)
-            .query(&[("take", "100")])
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        Ok(result.results)
+    pub async fn status(&self) -> Result<()> {
+        observe("overseerr", "status", async {
+            self.client
+                .get(format!("{}/api/v1/status", self.url))
+                .header("X-Api-Key", &self.api_key)
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+        .await
     }
 
+    /// A page of Overseerr users, `users` streams every user.
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn get_users_page(&self, skip: usize, take: usize) -> Result<Vec<User>> {
+        observe("overseerr", "get_users_page", async {
+            let result: ApiResponse<User> = self
+                .client
+                .get(format!("{}/api/v1/user", self.url))
+                .header("X-Api-Key", &self.api_key)
+                .query(&[("skip", skip), ("take", take)])
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(result.results)
+        })
+        .await
+    }
+
+    /// Every Overseerr user, fetched a page at a time.
+    pub fn users(&self) -> impl Stream<Item = Result<User>> + '_ {
+        paginate(PAGE_SIZE, move |skip| self.get_users_page(skip, PAGE_SIZE))
+    }
+
+    /// Find the Overseerr user which signs in with the given Plex account.
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn get_user_by_plex_id(&self, plex_user_id: &str) -> Result<Option<User>> {
+        let users = self.users();
+        let mut users = pin!(users);
+        while let Some(user) = users.try_next().await? {
+            if user.plex_id.to_string() == plex_user_id {
+                return Ok(Some(user));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Search for movies or TV shows, people are never returned.
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn search(&self, query: &str, media_type: MediaType) -> Result<Vec<Media>> {
+        observe("overseerr", "search", async {
+            let result: ApiResponse<Media> = self
+                .client
+                .get(format!("{}/api/v1/search", self.url))
+                .header("X-Api-Key", &self.api_key)
+                .query(&[("query", query), ("page", "1")])
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(result
+                .results
+                .into_iter()
+                .filter(|media| media.media_type == Some(media_type))
+                .collect())
+        })
+        .await
+    }
+
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn get_media(&self, media_type: MediaType, id: i64) -> Result<Media> {
+        observe("overseerr", "get_media", async {
+            Ok(self
+                .client
+                .get(format!("{}/api/v1/{media_type}/{id}", self.url))
+                .header("X-Api-Key", &self.api_key)
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?)
+        })
+        .await
+    }
+
+    /// Request a movie or every season of a TV show on behalf of an Overseerr user, so their
+    /// request quotas apply.
+    ///
+    /// Errors contain Overseerr's message, e.g. when the quota has been reached.
     #[instrument(skip(self), ret)]
-    pub async fn set_request_tier(&self, user: &User) -> Result<()> {
-        let watch_stats = self
+    pub async fn create_request(
+        &self,
+        user_id: i64,
+        media_type: MediaType,
+        media_id: i64,
+    ) -> Result<MediaRequest> {
+        observe("overseerr", "create_request", async {
+            let response = self
+                .client
+                .post(format!("{}/api/v1/request", self.url))
+                .header("X-Api-Key", &self.api_key)
+                .json(&CreateRequest {
+                    media_type,
+                    media_id,
+                    seasons: match media_type {
+                        MediaType::Tv => Some(String::from("all")),
+                        _ => None,
+                    },
+                    user_id,
+                })
+                .inject_trace_context()
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                let status = response.status();
+                match response.json::<ErrorResponse>().await {
+                    Ok(err) => anyhow::bail!("{}", err.message),
+                    Err(_) => anyhow::bail!("request failed with status {status}"),
+                }
+            }
+            Ok(response.json().await?)
+        })
+        .await
+    }
+
+    /// Look up a request, including the user who made it.
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn get_request(&self, request_id: &str) -> Result<MediaRequest> {
+        observe("overseerr", "get_request", async {
+            Ok(self
+                .client
+                .get(format!("{}/api/v1/request/{request_id}", self.url))
+                .header("X-Api-Key", &self.api_key)
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?)
+        })
+        .await
+    }
+
+    /// What the user has watched and the Discord roles they hold, and the request tier they
+    /// should be on for them. `None` is Overseerr's default quotas.
+    #[instrument(skip(self), ret)]
+    pub async fn target_request_tier(
+        &self,
+        user: &User,
+    ) -> Result<(TierStanding, Option<RequestLimitTier>)> {
+        let requests_config = &self.config.requests_config;
+        let plex_user_id = user.plex_id.to_string();
+        let mut standing = TierStanding {
+            plex_username: user.plex_username.clone(),
+            ..Default::default()
+        };
+
+        let mut keys = requests_config.watch_hours_keys();
+        // Always looked up as it's what users are shown.
+        keys.insert(WatchHoursKey {
+            media: WatchMedia::All,
+            window_days: None,
+        });
+        for key in keys {
+            let hours = self.watch_hours(&plex_user_id, key).await?;
+            standing.watch_hours.insert(key, hours);
+        }
+        if requests_config.uses_discord_roles() {
+            standing.discord_roles = self.discord_roles(user).await?;
+        }
+
+        let request_tier = requests_config.tier_for(&standing).cloned();
+        Ok((standing, request_tier))
+    }
+
+    async fn watch_hours(&self, plex_user_id: &str, key: WatchHoursKey) -> Result<i64> {
+        let media_type = match key.media {
+            WatchMedia::All => {
+                let query_days = key.window_days.map_or(QueryDays::Total, QueryDays::Days);
+                let watch_stats = self
+                    .tautulli_service
+                    .get_user_watch_time_stats(plex_user_id, Some(true), Some(query_days))
+                    .await?;
+                let latest_stat = watch_stats
+                    .first()
+                    .ok_or_else(|| anyhow::anyhow!("failed to fetch stats"))?;
+                return Ok((latest_stat.total_time / 3600).into());
+            }
+            WatchMedia::Movie => TautulliMediaType::Movie,
+            WatchMedia::Episode => TautulliMediaType::Episode,
+        };
+        let start_date = key
+            .window_days
+            .map(|days| (Utc::now() - chrono::Duration::days(days.into())).date_naive());
+        let seconds = self
             .tautulli_service
-            .get_user_watch_time_stats(
-                &user.plex_id.to_string(),
-                Some(true),
-                Some(QueryDays::Total),
-            )
+            .user_history(plex_user_id, media_type, start_date.as_ref())
+            .try_fold(0, |seconds, item| async move {
+                Ok(seconds + item.play_duration)
+            })
             .await?;
+        Ok(seconds / 3600)
+    }
 
-        let latest_stat = watch_stats
-            .first()
-            .ok_or_else(|| anyhow::anyhow!("failed to fetch stats"))?;
+    /// The user's linked Plex account, `None` if they haven't linked their Discord account.
+    pub async fn linked_plex_user(&self, user: &User) -> Result<Option<plex_user::Model>> {
+        let result = self
+            .plex_users_service
+            .get(&user.plex_id.to_string())
+            .await
+            .map_err(|err| anyhow::anyhow!(err.message))?;
+        match result {
+            GetPlexUserResult::Ok(plex_user) => Ok(Some(plex_user)),
+            GetPlexUserResult::Err(err) => match err.error {
+                GetPlexUserVariant::UserDoesNotExist => Ok(None),
+                GetPlexUserVariant::InternalError => {
+                    anyhow::bail!("unable to look up Plex user {}", user.plex_id)
+                }
+            },
+        }
+    }
 
-        let watch_hours = latest_stat.total_time / 3600;
-        let request_tier = &self
+    /// The ids and names of the user's roles in the Discord server, none if they haven't linked
+    /// their account or aren't in the server.
+    async fn discord_roles(&self, user: &User) -> Result<Vec<String>> {
+        let Some(plex_user) = self.linked_plex_user(user).await? else {
+            return Ok(vec![]);
+        };
+        let guild_id = self.config.discord.server_id;
+        let role_ids = match self
+            .discord_service
+            .get_member_role_ids(guild_id, plex_user.discord_user_id.parse()?)
+            .await
+        {
+            Ok(role_ids) => role_ids,
+            Err(err) if is_unknown_member(&err) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        // Only look up the guild's roles if some are configured by name.
+        let configured_by_name = self
             .config
             .requests_config
-            .overrides
-            .get(&user.plex_username)
-            .or_else(|| {
-                self.config
-                    .requests_config
-                    .tiers
+            .tiers
+            .iter()
+            .flat_map(|tier| &tier.discord_roles)
+            .any(|role| role.parse::<u64>().is_err());
+        let guild_roles = if configured_by_name {
+            self.discord_service.get_guild_roles(guild_id).await?
+        } else {
+            vec![]
+        };
+        Ok(role_ids
+            .into_iter()
+            .flat_map(|role_id| {
+                let name = guild_roles
                     .iter()
-                    .rev()
-                    .find(|&tier| tier.watch_hours < watch_hours.into())
-            });
+                    .find(|role| role.id.get() == role_id)
+                    .map(|role| role.name.clone());
+                [Some(role_id.to_string()), name].into_iter().flatten()
+            })
+            .collect())
+    }
 
-        if let Some(tier) = request_tier {
+    /// Set the user's request quotas for their watch hours. When their tier changes it's recorded,
+    /// and they're sent a DM if they've linked their Discord account.
+    #[instrument(skip(self), ret)]
+    pub async fn set_request_tier(&self, user: &User) -> Result<()> {
+        let (standing, request_tier) = self.target_request_tier(user).await?;
+        let watch_hours = standing.total_watch_hours();
+        if let Some(tier) = &request_tier {
             tracing::info!(
                 "Setting user ({}:{}) to tier {}",
                 user.display_name,
                 watch_hours,
                 tier.name
             );
-            self.set_user_request_settings(
-                &user.id.to_string(),
-                &UserRequestSettings {
-                    movie_quota_limit: Some(tier.movie.quota_limit),
-                    movie_quota_days: Some(tier.movie.quota_days),
-                    tv_quota_limit: Some(tier.tv.quota_limit),
-                    tv_quota_days: Some(tier.tv.quota_days),
-                },
-            )
-            .await?;
+            self.set_user_request_settings(&user.id.to_string(), &tier.into())
+                .await?;
         } else {
             tracing::info!("Setting user {} to default tier", user.display_name);
             self.set_default_request_settings(user).await?;
         }
+
+        let plex_user_id = user.plex_id.to_string();
+        let tier_name = request_tier.as_ref().map(|tier| tier.name.clone());
+        let previous = self
+            .request_tier_assignments_service
+            .latest(&plex_user_id)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.message))?;
+        if matches!(&previous, Some(previous) if previous.tier == tier_name) {
+            return Ok(());
+        }
+        self.request_tier_assignments_service
+            .create(
+                user.id,
+                &plex_user_id,
+                &user.plex_username,
+                tier_name,
+                watch_hours,
+            )
+            .await
+            .map_err(|err| anyhow::anyhow!(err.message))?;
+        // Users are only told about changes, not the first tier they're put on.
+        if let Some(previous) = previous {
+            self.notify_tier_change(
+                user,
+                previous.tier.as_deref(),
+                request_tier.as_ref(),
+                &standing,
+            )
+            .await;
+        }
         Ok(())
     }
 
+    /// DM the user about their new tier, failures are only logged as the tier has already been
+    /// set.
+    async fn notify_tier_change(
+        &self,
+        user: &User,
+        previous: Option<&str>,
+        tier: Option<&RequestLimitTier>,
+        standing: &TierStanding,
+    ) {
+        let discord_user_id = match self.linked_plex_user(user).await {
+            Ok(Some(plex_user)) => plex_user.discord_user_id,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!("{err:?}");
+                return;
+            }
+        };
+        let Ok(discord_user_id) = discord_user_id.parse() else {
+            return;
+        };
+        let next = self.config.requests_config.next_tier(standing);
+        let message = notifications::request_tier_changed(previous, tier, next, standing);
+        if let Err(err) = self
+            .discord_service
+            .send_direct_message(discord_user_id, message)
+            .await
+        {
+            tracing::warn!("unable to DM {discord_user_id} about their request tier: {err:?}");
+        }
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn get_user_request_settings(&self, user_id: &str) -> Result<UserRequestSettings> {
+        observe("overseerr", "get_user_request_settings", async {
+            Ok(self
+                .client
+                .get(format!(
+                    "{}/api/v1/user/{}/settings/main",
+                    self.url, user_id
+                ))
+                .header("X-Api-Key", &self.api_key)
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?)
+        })
+        .await
+    }
+
     #[instrument(skip(self), ret)]
     pub async fn set_user_request_settings(
         &self,
         user_id: &str,
         request_settings: &UserRequestSettings,
     ) -> Result<()> {
-        self.client
-            .post(format!(
-                "{}/api/v1/user/{}/settings/main",
-                self.url, user_id
-            ))
-            .header("X-Api-Key", &self.api_key)
-            .json(&request_settings)
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+        observe("overseerr", "set_user_request_settings", async {
+            self.client
+                .post(format!(
+                    "{}/api/v1/user/{}/settings/main",
+                    self.url, user_id
+                ))
+                .header("X-Api-Key", &self.api_key)
+                .json(&request_settings)
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+        .await
     }
 
     #[instrument(skip(self), ret)]
     pub async fn set_default_request_settings(&self, user: &User) -> Result<()> {
-        self.set_user_request_settings(
-            &user.id.to_string(),
-            &UserRequestSettings {
-                movie_quota_limit: None,
-                movie_quota_days: None,
-                tv_quota_limit: None,
-                tv_quota_days: None,
-            },
-        )
-        .await?;
+        self.set_user_request_settings(&user.id.to_string(), &UserRequestSettings::default())
+            .await?;
         Ok(())
     }
 
@@ -155,11 +508,7 @@ impl OverseerrService {
             "Setting Overseerr settings... discord: {}, plex: {}",
             discord_user_id, plex_user_id
         );
-        let overseerr_user = self
-            .get_users()
-            .await?
-            .into_iter()
-            .find(|u| u.plex_id.to_string() == plex_user_id);
+        let overseerr_user = self.get_user_by_plex_id(plex_user_id).await?;
         if let Some(user) = overseerr_user {
             info!("Found Overseerr user: {:#?}", user);
             self.set_request_tier(&user).await?;
@@ -1,8 +1,11 @@
+use derive_more::Display;
 use serde::{
     Deserialize,
     Serialize,
 };
 
+use crate::config::RequestLimitTier;
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
@@ -17,6 +20,130 @@ pub struct ApiResponse<T> {
     pub results: Vec<T>,
 }
 
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    #[display(fmt = "movie")]
+    Movie,
+    #[display(fmt = "tv")]
+    Tv,
+    #[display(fmt = "person")]
+    Person,
+}
+
+/// A movie or TV show, either from a search or from the details endpoints.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Media {
+    pub id: i64,
+    /// Only set on search results.
+    pub media_type: Option<MediaType>,
+    /// Set for movies.
+    pub title: Option<String>,
+    /// Set for TV shows.
+    pub name: Option<String>,
+    pub release_date: Option<String>,
+    pub first_air_date: Option<String>,
+    pub overview: Option<String>,
+    pub poster_path: Option<String>,
+    pub media_info: Option<MediaInfo>,
+}
+
+impl Media {
+    /// The title along with the year it was released, e.g. `The Matrix (1999)`.
+    pub fn display_title(&self) -> String {
+        let title = self
+            .title
+            .as_deref()
+            .or(self.name.as_deref())
+            .unwrap_or("Unknown");
+        match self
+            .release_date
+            .as_deref()
+            .or(self.first_air_date.as_deref())
+            .and_then(|date| date.get(..4))
+        {
+            Some(year) => format!("{title} ({year})"),
+            None => title.to_owned(),
+        }
+    }
+
+    pub fn poster_url(&self) -> Option<String> {
+        self.poster_path
+            .as_ref()
+            .map(|path| format!("https://image.tmdb.org/t/p/w300{path}"))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    pub status: MediaStatus,
+}
+
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "i32", into = "i32")]
+pub enum MediaStatus {
+    #[display(fmt = "Unknown")]
+    Unknown,
+    #[display(fmt = "Pending")]
+    Pending,
+    #[display(fmt = "Processing")]
+    Processing,
+    #[display(fmt = "Partially Available")]
+    PartiallyAvailable,
+    #[display(fmt = "Available")]
+    Available,
+}
+
+impl From<i32> for MediaStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            2 => MediaStatus::Pending,
+            3 => MediaStatus::Processing,
+            4 => MediaStatus::PartiallyAvailable,
+            5 => MediaStatus::Available,
+            _ => MediaStatus::Unknown,
+        }
+    }
+}
+
+impl From<MediaStatus> for i32 {
+    fn from(value: MediaStatus) -> Self {
+        match value {
+            MediaStatus::Unknown => 1,
+            MediaStatus::Pending => 2,
+            MediaStatus::Processing => 3,
+            MediaStatus::PartiallyAvailable => 4,
+            MediaStatus::Available => 5,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRequest {
+    pub media_type: MediaType,
+    pub media_id: i64,
+    /// Only used for TV shows, `all` requests every season.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seasons: Option<String>,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaRequest {
+    pub id: i64,
+    pub status: i32,
+    pub requested_by: Option<User>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    pub message: String,
+}
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct VerifiedUserRequest {
     pub plex_user_id: String,
@@ -33,7 +160,8 @@ pub struct UpdateUserSettingsRequest {
     pub tv_quota_days: Option<i64>,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// A user's request quotas, `None` uses Overseerr's global default.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserRequestSettings {
     pub movie_quota_limit: Option<i64>,
@@ -42,6 +170,17 @@ pub struct UserRequestSettings {
     pub tv_quota_days: Option<i64>,
 }
 
+impl From<&RequestLimitTier> for UserRequestSettings {
+    fn from(tier: &RequestLimitTier) -> Self {
+        Self {
+            movie_quota_limit: Some(tier.movie.quota_limit),
+            movie_quota_days: Some(tier.movie.quota_days),
+            tv_quota_limit: Some(tier.tv.quota_limit),
+            tv_quota_days: Some(tier.tv.quota_days),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserSettingsResponse {
@@ -51,3 +190,39 @@ pub struct UpdateUserSettingsResponse {
     pub watchlist_sync_movies: Option<String>,
     pub watchlist_sync_tv: Option<String>,
 }
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WebhookNotificationType {
+    MediaPending,
+    MediaApproved,
+    MediaAutoApproved,
+    MediaAvailable,
+    MediaDeclined,
+    MediaFailed,
+    TestNotification,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+/// Payload sent by Overseerr's webhook agent when using its default JSON template.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhookPayload {
+    pub notification_type: WebhookNotificationType,
+    pub subject: String,
+    pub message: Option<String>,
+    pub image: Option<String>,
+    pub request: Option<WebhookRequest>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhookRequest {
+    pub request_id: String,
+    #[serde(rename = "requestedBy_username")]
+    pub requested_by_username: String,
+    #[serde(rename = "requestedBy_settings_discordId")]
+    pub requested_by_discord_id: Option<String>,
+}
@@ -0,0 +1,90 @@
+use chrono::Utc;
+use serenity::all::{
+    CreateEmbed,
+    CreateEmbedFooter,
+    CreateMessage,
+};
+
+use crate::config::{
+    RequestLimit,
+    RequestLimitTier,
+    TierStanding,
+    WatchMedia,
+};
+
+/// Build the DM sent when a user's Overseerr request tier changes.
+///
+/// # Arguments
+///
+/// * `previous` - The name of the tier the user was on, `None` for the default quotas
+/// * `tier` - The tier the user is now on, `None` for the default quotas
+/// * `next` - The tier the user moves up to once they watch more, if any
+/// * `standing` - What the user has watched
+pub fn request_tier_changed(
+    previous: Option<&str>,
+    tier: Option<&RequestLimitTier>,
+    next: Option<&RequestLimitTier>,
+    standing: &TierStanding,
+) -> CreateMessage {
+    let tier_name = |name: Option<&str>| match name {
+        Some(name) => format!("**{name}**"),
+        None => String::from("the **default** tier"),
+    };
+    let quota = |limit: Option<&RequestLimit>| match limit {
+        Some(limit) if limit.quota_limit == 0 => String::from("Unlimited"),
+        Some(limit) => format!(
+            "{} every {} day{}",
+            limit.quota_limit,
+            limit.quota_days,
+            if limit.quota_days == 1 { "" } else { "s" }
+        ),
+        None => String::from("Server default"),
+    };
+
+    let embed = CreateEmbed::new()
+        .title("🎟️ Request Tier Changed")
+        .description(format!(
+            "You've moved from {} to {} with {} watch hours.",
+            tier_name(previous),
+            tier_name(tier.map(|tier| tier.name.as_str())),
+            standing.total_watch_hours(),
+        ))
+        .color(0x00A8FC) // Plex blue
+        .field("Movie Requests", quota(tier.map(|tier| &tier.movie)), true)
+        .field("TV Requests", quota(tier.map(|tier| &tier.tv)), true)
+        .field(
+            "Next Tier",
+            match next {
+                Some(next) => {
+                    let remaining: Vec<String> = next
+                        .thresholds()
+                        .into_iter()
+                        .filter_map(|(key, hours)| {
+                            let more = hours - standing.hours(key) + 1;
+                            let media = match key.media {
+                                WatchMedia::All => "watch",
+                                WatchMedia::Movie => "movie",
+                                WatchMedia::Episode => "episode",
+                            };
+                            (more > 0).then(|| format!("{more} more {media} hours"))
+                        })
+                        .collect();
+                    let window = next
+                        .window_days
+                        .map(|days| format!(" in the last {days} days"))
+                        .unwrap_or_default();
+                    format!(
+                        "**{}** after {}{window}.",
+                        next.name,
+                        remaining.join(" and ")
+                    )
+                }
+                None => String::from("There are no more tiers to move up to."),
+            },
+            false,
+        )
+        .footer(CreateEmbedFooter::new("powered by displex"))
+        .timestamp(Utc::now());
+
+    CreateMessage::new().embed(embed)
+}
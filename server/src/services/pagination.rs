@@ -0,0 +1,61 @@
+use std::future::Future;
+
+use async_graphql::futures_util::{
+    stream,
+    Stream,
+    TryStreamExt,
+};
+
+/// Stream every item of a paginated listing, fetching `page_size` items at a time from the given
+/// offset until a page comes back short.
+pub fn paginate<'a, T, E, F, Fut>(
+    page_size: usize,
+    fetch: F,
+) -> impl Stream<Item = Result<T, E>> + 'a
+where
+    T: 'a,
+    E: 'a,
+    F: Fn(usize) -> Fut + 'a,
+    Fut: Future<Output = Result<Vec<T>, E>> + 'a,
+{
+    stream::try_unfold((fetch, Some(0)), move |(fetch, offset)| async move {
+        let Some(offset) = offset else {
+            return Ok(None);
+        };
+        let page = fetch(offset).await?;
+        let next = (page.len() >= page_size).then_some(offset + page.len());
+        Ok(Some((
+            stream::iter(page.into_iter().map(Ok)),
+            (fetch, next),
+        )))
+    })
+    .try_flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn paginate_works() {
+        for (total, expected_fetches) in [(0, 1), (99, 1), (100, 2), (250, 3)] {
+            let items: Vec<usize> = (0..total).collect();
+            let fetches = AtomicUsize::new(0);
+            let result: Vec<usize> = paginate(100, |offset| {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                let page = items.iter().copied().skip(offset).take(100).collect();
+                async move { Ok::<_, ()>(page) }
+            })
+            .try_collect()
+            .await
+            .unwrap();
+            assert_eq!(result, items);
+            assert_eq!(fetches.load(Ordering::SeqCst), expected_fetches);
+        }
+    }
+}
@@ -5,6 +5,11 @@ use anyhow::Result;
 use reqwest::Url;
 use tracing::instrument;
 
+use crate::telemetry::{
+    observe,
+    InjectTraceContext,
+};
+
 use self::{
     constants::{
         PLEX_TV_APP_URL,
@@ -43,20 +48,24 @@ impl PlexService {
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn get_pin(&self) -> Result<CreatePinResponse> {
-        let form_params = [
-            ("strong", "true"),
-            ("X-Plex-Product", &self.client_id),
-            ("X-Plex-Client-Identifier", &self.client_id),
-        ];
+        observe("plex", "get_pin", async {
+            let form_params = [
+                ("strong", "true"),
+                ("X-Plex-Product", &self.client_id),
+                ("X-Plex-Client-Identifier", &self.client_id),
+            ];
 
-        Ok(self
-            .client
-            .post(format!("{PLEX_TV_URL}{PLEX_TV_PIN_PATH}"))
-            .form(&form_params)
-            .send()
-            .await?
-            .json()
-            .await?)
+            Ok(self
+                .client
+                .post(format!("{PLEX_TV_URL}{PLEX_TV_PIN_PATH}"))
+                .form(&form_params)
+                .inject_trace_context()
+                .send()
+                .await?
+                .json()
+                .await?)
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
@@ -90,51 +99,69 @@ impl PlexService {
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn pin_claim(&self, pin_id: u64, pin_code: &str) -> Result<PinClaimResponse> {
-        let params: [(&str, &str); 3] = [
-            ("X-Plex-Product", &self.client_id),
-            ("X-Plex-Client-Identifier", &self.client_id),
-            ("code", pin_code),
-        ];
-        let url = Url::parse_with_params(
-            &format!("{PLEX_TV_URL}{PLEX_TV_PIN_PATH}/{pin_id}"),
-            &params,
-        )?;
+        observe("plex", "pin_claim", async {
+            let params: [(&str, &str); 3] = [
+                ("X-Plex-Product", &self.client_id),
+                ("X-Plex-Client-Identifier", &self.client_id),
+                ("code", pin_code),
+            ];
+            let url = Url::parse_with_params(
+                &format!("{PLEX_TV_URL}{PLEX_TV_PIN_PATH}/{pin_id}"),
+                &params,
+            )?;
 
-        tracing::debug!("pin_claim: {}", url);
-        Ok(self.client.get(url).send().await?.json().await?)
+            tracing::debug!("pin_claim: {}", url);
+            Ok(self
+                .client
+                .get(url)
+                .inject_trace_context()
+                .send()
+                .await?
+                .json()
+                .await?)
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn user(&self, auth_token: &str) -> Result<User> {
-        let user_params: [(&str, &str); 3] = [
-            ("X-Plex-Token", auth_token),
-            ("X-Plex-Product", &self.client_id),
-            ("X-Plex-Client-Identifier", &self.client_id),
-        ];
-        Ok(self
-            .client
-            .get(format!("{PLEX_TV_URL}{PLEX_TV_USER_PATH}"))
-            .query(&user_params)
-            .send()
-            .await?
-            .json()
-            .await?)
+        observe("plex", "user", async {
+            let user_params: [(&str, &str); 3] = [
+                ("X-Plex-Token", auth_token),
+                ("X-Plex-Product", &self.client_id),
+                ("X-Plex-Client-Identifier", &self.client_id),
+            ];
+            Ok(self
+                .client
+                .get(format!("{PLEX_TV_URL}{PLEX_TV_USER_PATH}"))
+                .query(&user_params)
+                .inject_trace_context()
+                .send()
+                .await?
+                .json()
+                .await?)
+        })
+        .await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn get_devices(&self, auth_token: &str) -> Result<Vec<Device>> {
-        let user_params: [(&str, &str); 3] = [
-            ("X-Plex-Token", auth_token),
-            ("X-Plex-Product", &self.client_id),
-            ("X-Plex-Client-Identifier", &self.client_id),
-        ];
-        Ok(self
-            .client
-            .get(format!("{PLEX_TV_URL}{PLEX_TV_RESOURCES_PATH}"))
-            .query(&user_params)
-            .send()
-            .await?
-            .json()
-            .await?)
+        observe("plex", "get_devices", async {
+            let user_params: [(&str, &str); 3] = [
+                ("X-Plex-Token", auth_token),
+                ("X-Plex-Product", &self.client_id),
+                ("X-Plex-Client-Identifier", &self.client_id),
+            ];
+            Ok(self
+                .client
+                .get(format!("{PLEX_TV_URL}{PLEX_TV_RESOURCES_PATH}"))
+                .query(&user_params)
+                .inject_trace_context()
+                .send()
+                .await?
+                .json()
+                .await?)
+        })
+        .await
     }
 }
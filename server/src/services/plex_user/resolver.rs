@@ -38,7 +38,7 @@ impl PlexUsersQuery {
         gql_ctx: &Context<'_>,
         input: GetPlexUserInput,
     ) -> Result<GetPlexUserResult> {
-        verify_role(gql_ctx, Role::Admin)?;
+        verify_role(gql_ctx, Role::Moderator)?;
         gql_ctx
             .data_unchecked::<PlexUsersService>()
             .get(&input.id)
@@ -49,7 +49,7 @@ impl PlexUsersQuery {
         gql_ctx: &Context<'_>,
         input: ListPlexUserInput,
     ) -> Result<Vec<plex_user::Model>> {
-        verify_role(gql_ctx, Role::Admin)?;
+        verify_role(gql_ctx, Role::Moderator)?;
         gql_ctx
             .data_unchecked::<PlexUsersService>()
             .list(input.discord_user_id)
@@ -295,6 +295,14 @@ impl PlexUsersService {
         })
     }
 
+    #[instrument(skip(self), ret)]
+    pub async fn get_by_username(&self, username: &str) -> Result<Option<plex_user::Model>> {
+        Ok(PlexUser::find()
+            .filter(plex_user::Column::Username.eq(username))
+            .one(&self.db)
+            .await?)
+    }
+
     #[instrument(skip(self), ret)]
     pub async fn list(&self, discord_user_id: Option<String>) -> Result<Vec<plex_user::Model>> {
         Ok(PlexUser::find()
@@ -305,6 +313,16 @@ impl PlexUsersService {
             .await?)
     }
 
+    #[instrument(skip(self), ret)]
+    pub async fn count(&self, is_subscriber: Option<bool>) -> Result<u64> {
+        Ok(PlexUser::find()
+            .apply_if(is_subscriber, |query, value| {
+                query.filter(plex_user::Column::IsSubscriber.eq(value))
+            })
+            .count(&self.db)
+            .await?)
+    }
+
     #[instrument(skip(self), ret)]
     pub async fn update(
         &self,
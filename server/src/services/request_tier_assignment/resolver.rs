@@ -0,0 +1,125 @@
+use async_graphql::{
+    Context,
+    InputObject,
+    Object,
+    Result,
+};
+use chrono::Utc;
+use sea_orm::{
+    prelude::*,
+    ActiveValue,
+    QueryOrder,
+    QuerySelect,
+    QueryTrait,
+};
+use tracing::instrument;
+
+use crate::{
+    entities::{
+        prelude::*,
+        request_tier_assignment,
+    },
+    server::cookies::{
+        verify_role,
+        Role,
+    },
+};
+
+const DEFAULT_LIST_LIMIT: u64 = 50;
+const MAX_LIST_LIMIT: u64 = 500;
+
+#[derive(Default)]
+pub struct RequestTierAssignmentsQuery;
+
+#[Object]
+impl RequestTierAssignmentsQuery {
+    /// Request tier changes, most recent first.
+    async fn list_request_tier_assignments(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ListRequestTierAssignmentsInput,
+    ) -> Result<Vec<request_tier_assignment::Model>> {
+        verify_role(gql_ctx, Role::Admin)?;
+        gql_ctx
+            .data_unchecked::<RequestTierAssignmentsService>()
+            .list(
+                input.plex_user_id,
+                input
+                    .limit
+                    .unwrap_or(DEFAULT_LIST_LIMIT)
+                    .min(MAX_LIST_LIMIT),
+            )
+            .await
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct ListRequestTierAssignmentsInput {
+    pub plex_user_id: Option<String>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestTierAssignmentsService {
+    db: DatabaseConnection,
+}
+
+impl RequestTierAssignmentsService {
+    pub fn new(db: &DatabaseConnection) -> Self {
+        Self { db: db.clone() }
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn create(
+        &self,
+        overseerr_user_id: i64,
+        plex_user_id: &str,
+        plex_username: &str,
+        tier: Option<String>,
+        watch_hours: i64,
+    ) -> Result<request_tier_assignment::Model> {
+        let data = request_tier_assignment::ActiveModel {
+            overseerr_user_id: ActiveValue::Set(overseerr_user_id),
+            plex_user_id: ActiveValue::Set(plex_user_id.to_owned()),
+            plex_username: ActiveValue::Set(plex_username.to_owned()),
+            tier: ActiveValue::Set(tier),
+            watch_hours: ActiveValue::Set(watch_hours),
+            assigned_at: ActiveValue::Set(Utc::now()),
+            ..Default::default()
+        };
+        Ok(RequestTierAssignment::insert(data)
+            .exec_with_returning(&self.db)
+            .await?)
+    }
+
+    /// The tier the user was last assigned, if they've ever been assigned one.
+    #[instrument(skip(self), ret)]
+    pub async fn latest(
+        &self,
+        plex_user_id: &str,
+    ) -> Result<Option<request_tier_assignment::Model>> {
+        Ok(RequestTierAssignment::find()
+            .filter(request_tier_assignment::Column::PlexUserId.eq(plex_user_id))
+            .order_by_desc(request_tier_assignment::Column::AssignedAt)
+            .order_by_desc(request_tier_assignment::Column::Id)
+            .one(&self.db)
+            .await?)
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn list(
+        &self,
+        plex_user_id: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<request_tier_assignment::Model>> {
+        Ok(RequestTierAssignment::find()
+            .apply_if(plex_user_id, |query, value| {
+                query.filter(request_tier_assignment::Column::PlexUserId.eq(value))
+            })
+            .order_by_desc(request_tier_assignment::Column::AssignedAt)
+            .order_by_desc(request_tier_assignment::Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?)
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use anyhow::Result;
+use tracing::instrument;
+
+use crate::{
+    config::AppConfig,
+    server::cookies::Role,
+};
+
+use super::discord::{
+    is_unknown_member,
+    DiscordService,
+};
+
+/// How long to treat a user as a [`Role::User`] after failing to look up their roles, so an
+/// unavailable Discord isn't asked again on every request.
+const FAILED_LOOKUP_TTL: Duration = Duration::from_secs(30);
+
+/// Works out a user's [`Role`] from `api.admin_discord_ids` and their roles in the Discord guild,
+/// caching the guild roles for `api.role_cache_ttl`.
+#[derive(Debug, Clone)]
+pub struct RolesService {
+    config: AppConfig,
+    discord_service: DiscordService,
+    /// Role and when it expires, by Discord user id.
+    cache: Arc<Mutex<HashMap<String, (Instant, Role)>>>,
+}
+
+impl RolesService {
+    pub fn new(config: &AppConfig, discord_service: &DiscordService) -> Self {
+        Self {
+            config: config.clone(),
+            discord_service: discord_service.clone(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Users who aren't in the guild, or can't be looked up because Discord is unavailable, are
+    /// treated as a [`Role::User`].
+    #[instrument(skip(self))]
+    pub async fn role_for(&self, discord_user_id: &str) -> Role {
+        let api = &self.config.api;
+        if api.admin_discord_ids.iter().any(|id| id == discord_user_id) {
+            return Role::Admin;
+        }
+        if api.admin_roles.is_empty() && api.moderator_roles.is_empty() {
+            return Role::User;
+        }
+        if let Some((expires_at, role)) = self.cache.lock().unwrap().get(discord_user_id) {
+            if Instant::now() < *expires_at {
+                return *role;
+            }
+        }
+
+        let (role, ttl) = match self.fetch_role(discord_user_id).await {
+            Ok(role) => (role, api.role_cache_ttl),
+            Err(err) if is_unknown_member(&err) => (Role::User, api.role_cache_ttl),
+            Err(err) => {
+                tracing::warn!("unable to fetch guild roles for {discord_user_id}: {err:?}");
+                (Role::User, FAILED_LOOKUP_TTL.min(api.role_cache_ttl))
+            }
+        };
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        // Drop expired entries so users who stop visiting don't stay cached forever.
+        cache.retain(|_, (expires_at, _)| *expires_at > now);
+        cache.insert(discord_user_id.to_owned(), (now + ttl, role));
+        role
+    }
+
+    async fn fetch_role(&self, discord_user_id: &str) -> Result<Role> {
+        let guild_id = self.config.discord.server_id;
+        let member_role_ids = self
+            .discord_service
+            .get_member_role_ids(guild_id, discord_user_id.parse()?)
+            .await?;
+        let api = &self.config.api;
+        // Only look up the guild's roles if some are configured by name.
+        let configured_by_name = api
+            .admin_roles
+            .iter()
+            .chain(api.moderator_roles.iter())
+            .any(|role| role.parse::<u64>().is_err());
+        let guild_roles: Vec<(u64, String)> = if configured_by_name {
+            self.discord_service
+                .get_guild_roles(guild_id)
+                .await?
+                .into_iter()
+                .map(|role| (role.id.get(), role.name))
+                .collect()
+        } else {
+            vec![]
+        };
+        Ok(role_from_guild_roles(
+            &api.admin_roles,
+            &api.moderator_roles,
+            &member_role_ids,
+            &guild_roles,
+        ))
+    }
+}
+
+/// Match the member's role ids against the configured role names or ids.
+fn role_from_guild_roles(
+    admin_roles: &[String],
+    moderator_roles: &[String],
+    member_role_ids: &[u64],
+    guild_roles: &[(u64, String)],
+) -> Role {
+    let has_any = |configured: &[String]| {
+        member_role_ids.iter().any(|role_id| {
+            let name = guild_roles
+                .iter()
+                .find(|(id, _)| id == role_id)
+                .map(|(_, name)| name.as_str());
+            configured
+                .iter()
+                .any(|role| *role == role_id.to_string() || Some(role.as_str()) == name)
+        })
+    };
+    if has_any(admin_roles) {
+        Role::Admin
+    } else if has_any(moderator_roles) {
+        Role::Moderator
+    } else {
+        Role::User
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn role_from_guild_roles_works() {
+        let admin_roles = vec![String::from("1")];
+        let moderator_roles = vec![String::from("Moderators")];
+        let guild_roles = vec![(1, String::from("Admins")), (2, String::from("Moderators"))];
+
+        assert_eq!(
+            role_from_guild_roles(&admin_roles, &moderator_roles, &[1, 2], &guild_roles),
+            Role::Admin
+        );
+        assert_eq!(
+            role_from_guild_roles(&admin_roles, &moderator_roles, &[2], &guild_roles),
+            Role::Moderator
+        );
+        assert_eq!(
+            role_from_guild_roles(&admin_roles, &moderator_roles, &[3], &guild_roles),
+            Role::User
+        );
+        assert_eq!(
+            role_from_guild_roles(&admin_roles, &moderator_roles, &[], &guild_roles),
+            Role::User
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use async_graphql::{
+    Context,
+    InputObject,
+    Object,
+    Result,
+    SimpleObject,
+};
+use chrono::Utc;
+use sea_orm::{
+    prelude::*,
+    ActiveValue,
+    QueryOrder,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        prelude::*,
+        session,
+    },
+    server::cookies::{
+        verify_role,
+        Role,
+    },
+};
+
+#[derive(Default)]
+pub struct SessionsQuery;
+
+#[Object]
+impl SessionsQuery {
+    async fn list_sessions(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ListSessionsInput,
+    ) -> Result<Vec<session::Model>> {
+        verify_role(gql_ctx, Role::Moderator)?;
+        gql_ctx
+            .data_unchecked::<SessionsService>()
+            .list(&input.discord_user_id)
+            .await
+    }
+}
+
+#[derive(Default)]
+pub struct SessionsMutation;
+
+#[Object]
+impl SessionsMutation {
+    /// Sign the user out everywhere.
+    async fn revoke_sessions(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: RevokeSessionsInput,
+    ) -> Result<RevokeSessionsSuccess> {
+        verify_role(gql_ctx, Role::Admin)?;
+        let revoked = gql_ctx
+            .data_unchecked::<SessionsService>()
+            .revoke_all(&input.discord_user_id)
+            .await?;
+        Ok(RevokeSessionsSuccess { revoked })
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct ListSessionsInput {
+    pub discord_user_id: String,
+}
+
+#[derive(Debug, InputObject)]
+pub struct RevokeSessionsInput {
+    pub discord_user_id: String,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct RevokeSessionsSuccess {
+    pub revoked: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionsService {
+    db: DatabaseConnection,
+}
+
+impl SessionsService {
+    pub fn new(db: &DatabaseConnection) -> Self {
+        Self { db: db.clone() }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create(
+        &self,
+        discord_user_id: &str,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        ttl: Duration,
+    ) -> Result<session::Model> {
+        let now = Utc::now();
+        let data = session::ActiveModel {
+            id: ActiveValue::Set(format!(
+                "{}{}",
+                Uuid::new_v4().simple(),
+                Uuid::new_v4().simple()
+            )),
+            discord_user_id: ActiveValue::Set(discord_user_id.to_owned()),
+            plex_user_id: ActiveValue::Set(None),
+            user_agent: ActiveValue::Set(user_agent),
+            ip_address: ActiveValue::Set(ip_address),
+            created_at: ActiveValue::Set(now),
+            expires_at: ActiveValue::Set(now + chrono::Duration::from_std(ttl)?),
+        };
+        Ok(Session::insert(data).exec_with_returning(&self.db).await?)
+    }
+
+    /// Get the session if it hasn't expired or been revoked.
+    #[instrument(skip_all)]
+    pub async fn get_active(&self, id: &str) -> Result<Option<session::Model>> {
+        Ok(Session::find_by_id(id)
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .one(&self.db)
+            .await?)
+    }
+
+    #[instrument(skip(self, id))]
+    pub async fn set_plex_user(&self, id: &str, plex_user_id: &str) -> Result<()> {
+        Session::update(session::ActiveModel {
+            id: ActiveValue::Set(id.to_owned()),
+            plex_user_id: ActiveValue::Set(Some(plex_user_id.to_owned())),
+            ..Default::default()
+        })
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        Session::delete_by_id(id).exec(&self.db).await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn list(&self, discord_user_id: &str) -> Result<Vec<session::Model>> {
+        Ok(Session::find()
+            .filter(session::Column::DiscordUserId.eq(discord_user_id))
+            .order_by_desc(session::Column::CreatedAt)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Delete every session of the user, returning how many there were.
+    #[instrument(skip(self), ret)]
+    pub async fn revoke_all(&self, discord_user_id: &str) -> Result<u64> {
+        Ok(Session::delete_many()
+            .filter(session::Column::DiscordUserId.eq(discord_user_id))
+            .exec(&self.db)
+            .await?
+            .rows_affected)
+    }
+
+    #[instrument(skip(self), ret)]
+    pub async fn delete_expired(&self) -> Result<u64> {
+        Ok(Session::delete_many()
+            .filter(session::Column::ExpiresAt.lte(Utc::now()))
+            .exec(&self.db)
+            .await?
+            .rows_affected)
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use metrics::counter;
+use serde_json::Value;
+use tokio::sync::{
+    Mutex,
+    OnceCell,
+};
+
+use crate::config::TautulliCacheConfig;
+
+#[derive(Debug)]
+struct Entry {
+    cmd: &'static str,
+    value: Arc<OnceCell<Value>>,
+    expires_at: Instant,
+}
+
+/// Caches Tautulli responses by command and parameters. Concurrent requests for the same
+/// response share a single request to Tautulli.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    config: TautulliCacheConfig,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &TautulliCacheConfig) -> Self {
+        Self {
+            config: config.clone(),
+            entries: Default::default(),
+        }
+    }
+
+    fn ttl(&self, cmd: &str) -> Duration {
+        self.config
+            .ttls
+            .get(cmd)
+            .map(|ttl| **ttl)
+            .unwrap_or(self.config.ttl)
+    }
+
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        cmd: &'static str,
+        key: String,
+        fetch: F,
+    ) -> Result<Value, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, E>>,
+    {
+        let ttl = self.ttl(cmd);
+        if ttl.is_zero() {
+            return fetch().await;
+        }
+
+        let (value, result) = {
+            let now = Instant::now();
+            let mut entries = self.entries.lock().await;
+            entries.retain(|_, entry| entry.expires_at > now);
+            match entries.get(&key) {
+                Some(entry) if entry.value.initialized() => (entry.value.clone(), "hit"),
+                Some(entry) => (entry.value.clone(), "coalesced"),
+                None => {
+                    let value = Arc::new(OnceCell::new());
+                    entries.insert(
+                        key,
+                        Entry {
+                            cmd,
+                            value: value.clone(),
+                            expires_at: now + ttl,
+                        },
+                    );
+                    (value, "miss")
+                }
+            }
+        };
+        counter!("displex_tautulli_cache_requests_total", "cmd" => cmd, "result" => result)
+            .increment(1);
+        // Failed requests aren't cached, the next caller tries again.
+        Ok(value.get_or_try_init(fetch).await?.clone())
+    }
+
+    /// Drop the cached responses of a command, e.g. once Tautulli tells us playback has changed.
+    pub async fn invalidate(&self, cmd: &str) {
+        self.entries
+            .lock()
+            .await
+            .retain(|_, entry| entry.cmd != cmd);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{
+        AtomicU32,
+        Ordering,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn get_or_fetch_works() {
+        let config = TautulliCacheConfig {
+            ttl: Duration::from_secs(60),
+            ttls: HashMap::from([(String::from("get_activity"), Duration::ZERO.into())]),
+        };
+        let cache = ResponseCache::new(&config);
+        let fetches = AtomicU32::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            Ok::<_, anyhow::Error>(Value::from(1))
+        };
+
+        let (a, b) = tokio::join!(
+            cache.get_or_fetch("get_libraries", String::from("a"), fetch),
+            cache.get_or_fetch("get_libraries", String::from("a"), fetch),
+        );
+        assert_eq!(a.unwrap(), Value::from(1));
+        assert_eq!(b.unwrap(), Value::from(1));
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        cache
+            .get_or_fetch("get_libraries", String::from("b"), fetch)
+            .await
+            .unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        cache
+            .get_or_fetch("get_activity", String::from("c"), fetch)
+            .await
+            .unwrap();
+        cache
+            .get_or_fetch("get_activity", String::from("c"), fetch)
+            .await
+            .unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 4);
+
+        cache.invalidate("get_home_stats").await;
+        cache
+            .get_or_fetch("get_libraries", String::from("a"), fetch)
+            .await
+            .unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 4);
+
+        cache.invalidate("get_libraries").await;
+        cache
+            .get_or_fetch("get_libraries", String::from("a"), fetch)
+            .await
+            .unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 5);
+    }
+}
@@ -0,0 +1,79 @@
+use async_graphql::ErrorExtensions;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TautulliError {
+    /// Tautulli couldn't be reached, e.g. it is offline or timed out.
+    #[error("unable to reach Tautulli: {0}")]
+    Transport(#[source] reqwest::Error),
+    #[error("Tautulli responded with {0}")]
+    Status(StatusCode),
+    /// Tautulli handled the request but its result wasn't `success`, e.g. the user doesn't exist.
+    #[error("Tautulli returned an error: {0}")]
+    Api(String),
+    /// The response doesn't match our models, e.g. after a Tautulli upgrade.
+    #[error("unexpected response from Tautulli: {0}")]
+    Schema(#[from] serde_json::Error),
+}
+
+impl TautulliError {
+    /// Whether Tautulli is down, rather than it rejecting the request.
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            TautulliError::Transport(_) => true,
+            TautulliError::Status(status) => status.is_server_error(),
+            TautulliError::Api(_) | TautulliError::Schema(_) => false,
+        }
+    }
+
+    /// Machine readable code for the error, returned in GraphQL error extensions.
+    pub fn code(&self) -> &'static str {
+        match self {
+            _ if self.is_unavailable() => "TAUTULLI_UNAVAILABLE",
+            TautulliError::Transport(_) | TautulliError::Status(_) | TautulliError::Api(_) => {
+                "TAUTULLI_ERROR"
+            }
+            TautulliError::Schema(_) => "TAUTULLI_INVALID_RESPONSE",
+        }
+    }
+}
+
+impl From<reqwest::Error> for TautulliError {
+    fn from(err: reqwest::Error) -> Self {
+        match err.status() {
+            Some(status) => TautulliError::Status(status),
+            // The URL contains the API key, so keep it out of the logs.
+            None => TautulliError::Transport(err.without_url()),
+        }
+    }
+}
+
+impl ErrorExtensions for TautulliError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, e| e.set("code", self.code()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn code_works() {
+        assert_eq!(
+            TautulliError::Status(StatusCode::BAD_GATEWAY).code(),
+            "TAUTULLI_UNAVAILABLE"
+        );
+        assert_eq!(
+            TautulliError::Status(StatusCode::UNAUTHORIZED).code(),
+            "TAUTULLI_ERROR"
+        );
+        assert_eq!(
+            TautulliError::Api(String::from("Invalid user_id")).code(),
+            "TAUTULLI_ERROR"
+        );
+        let err = serde_json::from_str::<u32>("\"\"").unwrap_err();
+        assert_eq!(TautulliError::from(err).code(), "TAUTULLI_INVALID_RESPONSE");
+    }
+}
@@ -1,4 +1,9 @@
+pub mod cache;
+pub mod error;
 pub mod models;
 pub mod resolver;
 
-pub use resolver::TautulliService;
+pub use self::{
+    error::TautulliError,
+    resolver::TautulliService,
+};
@@ -1,3 +1,8 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+};
+
 use async_graphql::SimpleObject;
 use derive_more::Display;
 use serde::{
@@ -10,7 +15,7 @@ use serde::{
     Serialize,
 };
 
-#[derive(Debug, Display)]
+#[derive(Debug, Display, Clone, Copy)]
 pub enum QueryDays {
     #[display(fmt = "1")]
     Day,
@@ -20,9 +25,11 @@ pub enum QueryDays {
     Month,
     #[display(fmt = "0")]
     Total,
+    #[display(fmt = "{}", _0)]
+    Days(u32),
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Display, Clone, Copy)]
 pub enum MediaType {
     #[display(fmt = "movie")]
     Movie,
@@ -123,7 +130,12 @@ pub struct HomeStats {
 
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct StatRow {
+    #[serde(default)]
     pub title: String,
+    pub user_id: Option<i64>,
+    pub friendly_name: Option<String>,
+    pub total_plays: Option<i64>,
+    pub total_duration: Option<i64>,
 }
 
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize, SimpleObject)]
@@ -140,6 +152,37 @@ pub struct GetActivity {
     pub total_bandwidth: u32,
     pub lan_bandwidth: u32,
     pub wan_bandwidth: u32,
+    #[serde(default)]
+    pub sessions: Vec<ActivitySession>,
+}
+
+/// A stream which is currently playing on the Plex server.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize, SimpleObject)]
+#[serde(default)]
+pub struct ActivitySession {
+    pub session_key: String,
+    #[serde(deserialize_with = "number_from_str_or_int")]
+    pub user_id: i64,
+    pub user: String,
+    pub friendly_name: String,
+    pub media_type: String,
+    pub title: String,
+    pub full_title: String,
+    /// `playing`, `paused` or `buffering`.
+    pub state: String,
+    #[serde(deserialize_with = "number_from_str_or_int")]
+    pub progress_percent: u32,
+    pub player: String,
+    pub platform: String,
+    pub product: String,
+    /// `direct play`, `copy` or `transcode`.
+    pub transcode_decision: String,
+    pub quality_profile: String,
+    pub stream_video_full_resolution: String,
+    /// `lan` or `wan`.
+    pub location: String,
+    #[serde(deserialize_with = "number_from_str_or_int")]
+    pub bandwidth: u32,
 }
 
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
@@ -156,6 +199,15 @@ pub struct GetLibrary {
     pub is_active: bool,
 }
 
+/// A user's watch time over a period, used to rank users on the leaderboard.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TopUser {
+    pub user_id: i64,
+    pub friendly_name: String,
+    pub plays: i64,
+    pub duration: i64,
+}
+
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct UserTable {
     pub records_filtered: Option<i64>,
@@ -230,6 +282,27 @@ pub struct HistoryItem {
     pub year: Option<i32>,
 }
 
+/// Tautulli returns some numbers as strings, and an empty string when there is no value.
+fn number_from_str_or_int<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr + Default,
+    T::Err: Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrInt<T> {
+        Int(T),
+        Str(String),
+    }
+
+    match StrOrInt::<T>::deserialize(deserializer)? {
+        StrOrInt::Int(value) => Ok(value),
+        StrOrInt::Str(value) if value.is_empty() => Ok(T::default()),
+        StrOrInt::Str(value) => value.parse().map_err(de::Error::custom),
+    }
+}
+
 fn bool_from_int<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
@@ -243,3 +316,35 @@ where
         )),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn activity_serde_works() {
+        let json = r#"{
+            "stream_count": "1",
+            "stream_count_direct_play": 0,
+            "stream_count_direct_stream": 0,
+            "stream_count_transcode": 1,
+            "total_bandwidth": 8000,
+            "lan_bandwidth": 0,
+            "wan_bandwidth": 8000,
+            "sessions": [{
+                "session_key": "12",
+                "user_id": 1234,
+                "user": "someone",
+                "full_title": "Show - Episode",
+                "progress_percent": "45",
+                "bandwidth": ""
+            }]
+        }"#;
+        let activity: GetActivity = serde_json::from_str(json).unwrap();
+        let session = activity.sessions.first().unwrap();
+        assert_eq!(session.user_id, 1234);
+        assert_eq!(session.progress_percent, 45);
+        assert_eq!(session.bandwidth, 0);
+        assert_eq!(session.full_title, "Show - Episode");
+    }
+}
@@ -1,41 +1,77 @@
+use std::{
+    pin::pin,
+    time::Duration,
+};
+
 use async_graphql::{
+    futures_util::{
+        stream,
+        Stream,
+        TryStreamExt,
+    },
     Context,
     Enum,
+    ErrorExtensions,
     Object,
     SimpleObject,
+    Subscription,
     Union,
 };
 
 use chrono::Utc;
-use reqwest::Url;
 
 use serde::{
+    de::DeserializeOwned,
     Deserialize,
     Serialize,
 };
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::MissedTickBehavior,
+};
 use tracing::instrument;
 
 use crate::{
+    config::TautulliCacheConfig,
     server::cookies::{
         get_plex_id,
         verify_role,
         Role,
     },
-    services::tautulli::models::{
-        ApiResponse,
-        GetActivity,
-        GetLibrary,
-        HomeStats,
-        ServerStatus,
-        StatId,
-        UserTable,
-        UserWatchStat,
+    services::{
+        events::{
+            Event,
+            EventBus,
+            TautulliEvent,
+            TautulliEventKind,
+        },
+        pagination::paginate,
+        tautulli::{
+            cache::ResponseCache,
+            error::TautulliError,
+            models::{
+                ApiResponse,
+                GetActivity,
+                GetLibrary,
+                HomeStats,
+                ServerStatus,
+                StatId,
+                TopUser,
+                User,
+                UserTable,
+                UserWatchStat,
+            },
+        },
+    },
+    telemetry::{
+        observe,
+        InjectTraceContext,
     },
 };
-use anyhow::Result;
 
 use super::models::{
     GetHistory,
+    HistoryItem,
     MediaType,
     QueryDays,
 };
@@ -49,12 +85,13 @@ impl TautulliQuery {
         &self,
         gql_ctx: &Context<'_>,
     ) -> async_graphql::Result<GetPlexStatusResult> {
-        verify_role(gql_ctx, Role::Admin)?;
+        verify_role(gql_ctx, Role::Moderator)?;
         Ok(GetPlexStatusResult::Ok(
             gql_ctx
                 .data_unchecked::<TautulliService>()
                 .server_status()
-                .await?,
+                .await
+                .map_err(|err| err.extend())?,
         ))
     }
 
@@ -62,12 +99,13 @@ impl TautulliQuery {
         &self,
         gql_ctx: &Context<'_>,
     ) -> async_graphql::Result<GetPlexActivityResult> {
-        verify_role(gql_ctx, Role::Admin)?;
+        verify_role(gql_ctx, Role::Moderator)?;
         Ok(GetPlexActivityResult::Ok(
             gql_ctx
                 .data_unchecked::<TautulliService>()
                 .get_activity()
-                .await?,
+                .await
+                .map_err(|err| err.extend())?,
         ))
     }
 
@@ -76,7 +114,8 @@ impl TautulliQuery {
         let user_stats = gql_ctx
             .data_unchecked::<TautulliService>()
             .get_home_stats(Some(&plex_user))
-            .await?;
+            .await
+            .map_err(|err| err.extend())?;
 
         let mut top_media = TopMedia::default();
         for stat in user_stats {
@@ -109,13 +148,13 @@ impl TautulliQuery {
     ) -> async_graphql::Result<GetLeaderboardResult> {
         let plex_user = get_plex_id(gql_ctx)?;
 
-        let users_table = gql_ctx
+        let users = gql_ctx
             .data_unchecked::<TautulliService>()
-            .get_users_table(Some("duration"), Some("desc"))
-            .await?;
+            .users_table(Some("duration"), Some("desc"));
+        let mut users = pin!(users);
         let mut position = 1;
         let mut leaderboard = Leaderboard::default();
-        for user in users_table.data {
+        while let Some(user) = users.try_next().await.map_err(|err| err.extend())? {
             let user_id = user.user_id.to_string();
             if user_id.eq(&plex_user) {
                 leaderboard.watch_duration = user.duration;
@@ -135,9 +174,12 @@ impl TautulliQuery {
             .get_user_history(
                 &plex_user,
                 MediaType::Movie,
-                &(Utc::now() - chrono::Duration::days(90)).date_naive(),
+                Some(&(Utc::now() - chrono::Duration::days(90)).date_naive()),
+                0,
+                RECENT_HISTORY_LENGTH,
             )
-            .await?;
+            .await
+            .map_err(|err| err.extend())?;
 
         Ok(GetHistoryResult::Ok(GetHistorySuccess {
             recent: user_history
@@ -151,6 +193,70 @@ impl TautulliQuery {
     }
 }
 
+#[derive(Default)]
+pub struct TautulliSubscription;
+
+#[Subscription]
+impl TautulliSubscription {
+    /// Current activity on the Plex server, sent every `intervalSeconds` and as soon as a stream
+    /// starts or stops if the Tautulli webhook is configured.
+    async fn plex_activity(
+        &self,
+        gql_ctx: &Context<'_>,
+        #[graphql(default = 10, validator(minimum = 5))] interval_seconds: u64,
+    ) -> async_graphql::Result<impl Stream<Item = GetActivity>> {
+        verify_role(gql_ctx, Role::Moderator)?;
+        let tautulli_svc = gql_ctx.data_unchecked::<TautulliService>().clone();
+        let events = gql_ctx.data_unchecked::<EventBus>().subscribe();
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Ok(stream::unfold(
+            (tautulli_svc, events, interval),
+            |(tautulli_svc, mut events, mut interval)| async move {
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {},
+                        event = events.recv() => match event {
+                            Ok(Event::Tautulli(event)) if matches!(
+                                event.kind,
+                                TautulliEventKind::Play | TautulliEventKind::Stop
+                            ) => interval.reset(),
+                            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                            Err(RecvError::Closed) => return None,
+                        },
+                    }
+                    match tautulli_svc.get_activity().await {
+                        Ok(activity) => return Some((activity, (tautulli_svc, events, interval))),
+                        Err(err) => tracing::warn!("unable to fetch activity: {err:?}"),
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Events received from the Tautulli webhook, e.g. streams starting and stopping.
+    async fn stream_events(
+        &self,
+        gql_ctx: &Context<'_>,
+    ) -> async_graphql::Result<impl Stream<Item = TautulliEvent>> {
+        verify_role(gql_ctx, Role::Moderator)?;
+        let events = gql_ctx.data_unchecked::<EventBus>().subscribe();
+
+        Ok(stream::unfold(events, |mut events| async move {
+            loop {
+                match events.recv().await {
+                    Ok(Event::Tautulli(event)) => return Some((event, events)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("subscriber missed {skipped} events");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }))
+    }
+}
+
 #[derive(Debug, Union)]
 pub enum GetPlexStatusResult {
     Ok(ServerStatus),
@@ -257,20 +363,93 @@ pub enum GetPlexActivityVariant {
     InternalError,
 }
 
+const PAGE_SIZE: usize = 100;
+/// How many items `getHistory` returns, Tautulli's default page length.
+const RECENT_HISTORY_LENGTH: usize = 25;
+
 #[derive(Debug, Clone)]
 pub struct TautulliService {
     client: reqwest::Client,
     api_key: String,
     url: String,
+    cache: ResponseCache,
 }
 
 impl TautulliService {
-    pub fn new(client: &reqwest::Client, url: &str, api_key: &str) -> Self {
+    pub fn new(
+        client: &reqwest::Client,
+        url: &str,
+        api_key: &str,
+        cache_config: &TautulliCacheConfig,
+    ) -> Self {
         Self {
             client: client.clone(),
             api_key: String::from(api_key),
             url: String::from(url),
+            cache: ResponseCache::new(cache_config),
+        }
+    }
+
+    /// Call a Tautulli API command, the response may be served from the cache.
+    async fn request<T: DeserializeOwned>(
+        &self,
+        cmd: &'static str,
+        params: Vec<(&str, String)>,
+    ) -> Result<T, TautulliError> {
+        let key = params.iter().fold(String::from(cmd), |key, (name, value)| {
+            format!("{key}&{name}={value}")
+        });
+        let data = self
+            .cache
+            .get_or_fetch(cmd, key, || self.fetch(cmd, &params))
+            .await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Call a Tautulli API command without going through the cache, for responses which aren't
+    /// worth keeping such as pages of a long history.
+    async fn request_uncached<T: DeserializeOwned>(
+        &self,
+        cmd: &'static str,
+        params: Vec<(&str, String)>,
+    ) -> Result<T, TautulliError> {
+        Ok(serde_json::from_value(self.fetch(cmd, &params).await?)?)
+    }
+
+    /// Call Tautulli. Only this is recorded as an upstream request, so cache hits aren't counted.
+    async fn fetch(
+        &self,
+        cmd: &'static str,
+        params: &[(&str, String)],
+    ) -> Result<serde_json::Value, TautulliError> {
+        let body = observe("tautulli", cmd, async {
+            self.client
+                .get(format!("{}/api/v2", self.url))
+                .query(&[("apikey", self.api_key.as_str()), ("cmd", cmd)])
+                .query(params)
+                .inject_trace_context()
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await
+        })
+        .await?;
+        let response: ApiResponse<serde_json::Value> = serde_json::from_slice(&body)?;
+        if response.response.result != "success" {
+            return Err(TautulliError::Api(
+                response
+                    .response
+                    .message
+                    .unwrap_or_else(|| format!("{cmd} failed")),
+            ));
         }
+        Ok(response.response.data)
+    }
+
+    /// Drop the cached responses of a command, such as `get_activity`.
+    pub async fn invalidate(&self, cmd: &str) {
+        self.cache.invalidate(cmd).await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
@@ -279,13 +458,8 @@ impl TautulliService {
         user_id: &str,
         grouping: Option<bool>,
         query_days: Option<QueryDays>,
-    ) -> Result<Vec<UserWatchStat>> {
-        let user_id = user_id.to_string();
-        let mut params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "get_user_watch_time_stats".into()),
-            ("user_id", user_id),
-        ];
+    ) -> Result<Vec<UserWatchStat>, TautulliError> {
+        let mut params = vec![("user_id", user_id.to_string())];
         if let Some(grouping) = grouping {
             params.push((
                 "grouping",
@@ -298,112 +472,182 @@ impl TautulliService {
         if let Some(query_days) = query_days {
             params.push(("query_days", query_days.to_string()));
         }
-
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<Vec<UserWatchStat>> =
-            self.client.get(url).send().await?.json().await?;
-
-        Ok(response.response.data)
+        self.request("get_user_watch_time_stats", params).await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
-    pub async fn server_status(&self) -> Result<ServerStatus> {
-        let params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "server_status".into()),
-        ];
-
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<ServerStatus> = self.client.get(url).send().await?.json().await?;
-
-        Ok(response.response.data)
+    pub async fn server_status(&self) -> Result<ServerStatus, TautulliError> {
+        self.request("server_status", vec![]).await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
-    pub async fn get_activity(&self) -> Result<GetActivity> {
-        let params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "get_activity".into()),
-        ];
-
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<GetActivity> = self.client.get(url).send().await?.json().await?;
-
-        Ok(response.response.data)
+    pub async fn get_activity(&self) -> Result<GetActivity, TautulliError> {
+        self.request("get_activity", vec![]).await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
-    pub async fn get_libraries(&self) -> Result<Vec<GetLibrary>> {
-        let params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "get_libraries".into()),
-        ];
-
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<Vec<GetLibrary>> =
-            self.client.get(url).send().await?.json().await?;
-
-        Ok(response.response.data)
+    pub async fn get_libraries(&self) -> Result<Vec<GetLibrary>, TautulliError> {
+        self.request("get_libraries", vec![]).await
     }
 
     #[instrument(skip(self), ret, level = "debug")]
-    pub async fn get_home_stats(&self, user_id: Option<&str>) -> Result<Vec<HomeStats>> {
-        let mut params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "get_home_stats".into()),
-        ];
+    pub async fn get_home_stats(
+        &self,
+        user_id: Option<&str>,
+    ) -> Result<Vec<HomeStats>, TautulliError> {
+        let mut params = vec![];
         if let Some(user_id) = user_id {
-            params.append(&mut vec![("user_id", String::from(user_id))]);
+            params.push(("user_id", String::from(user_id)));
         };
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<Vec<HomeStats>> =
-            self.client.get(url).send().await?.json().await?;
+        self.request("get_home_stats", params).await
+    }
 
-        Ok(response.response.data)
+    /// A page of the users with the most watch time over the given period, most watched first.
+    ///
+    /// Tautulli's home stats can't look back over all time, so the users table is used instead
+    /// for [`QueryDays::Total`].
+    #[instrument(skip(self), ret, level = "debug")]
+    pub async fn get_top_users(
+        &self,
+        query_days: QueryDays,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<TopUser>, TautulliError> {
+        if let QueryDays::Total = query_days {
+            let users_table = self
+                .get_users_table(Some("duration"), Some("desc"), start, count)
+                .await?;
+            return Ok(users_table
+                .data
+                .into_iter()
+                .map(|user| TopUser {
+                    user_id: user.user_id,
+                    friendly_name: user.friendly_name,
+                    plays: user.plays,
+                    duration: user.duration,
+                })
+                .collect());
+        }
+
+        let params = vec![
+            ("stat_id", "top_users".into()),
+            ("stats_type", "duration".into()),
+            ("stats_start", start.to_string()),
+            ("stats_count", count.to_string()),
+            ("time_range", query_days.to_string()),
+        ];
+        let home_stats: Vec<HomeStats> = self.request("get_home_stats", params).await?;
+
+        Ok(home_stats
+            .into_iter()
+            .filter(|stat| stat.stat_id == StatId::TopUsers)
+            .flat_map(|stat| stat.rows)
+            .filter_map(|row| {
+                Some(TopUser {
+                    user_id: row.user_id?,
+                    friendly_name: row.friendly_name.unwrap_or_default(),
+                    plays: row.total_plays.unwrap_or_default(),
+                    duration: row.total_duration.unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    /// Every user with watch time over the given period, most watched first, fetched a page at a
+    /// time.
+    pub fn top_users(
+        &self,
+        query_days: QueryDays,
+    ) -> impl Stream<Item = Result<TopUser, TautulliError>> + '_ {
+        paginate(PAGE_SIZE, move |start| {
+            self.get_top_users(query_days, start, PAGE_SIZE)
+        })
     }
 
+    /// A page of the users table, `users_table` streams every user.
     #[instrument(skip(self), ret, level = "debug")]
     pub async fn get_users_table(
         &self,
         order_column: Option<&str>,
         order_dir: Option<&str>,
-    ) -> Result<UserTable> {
-        let mut params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "get_users_table".into()),
-            ("length", "100".into()),
-        ];
+        start: usize,
+        length: usize,
+    ) -> Result<UserTable, TautulliError> {
+        let mut params = vec![("start", start.to_string()), ("length", length.to_string())];
         if let Some(order_column) = order_column {
-            params.append(&mut vec![("order_column", String::from(order_column))]);
+            params.push(("order_column", String::from(order_column)));
         };
         if let Some(order_dir) = order_dir {
-            params.append(&mut vec![("order_dir", String::from(order_dir))]);
+            params.push(("order_dir", String::from(order_dir)));
         };
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<UserTable> = self.client.get(url).send().await?.json().await?;
+        self.request("get_users_table", params).await
+    }
 
-        Ok(response.response.data)
+    /// Every user in the users table, fetched a page at a time.
+    pub fn users_table<'a>(
+        &'a self,
+        order_column: Option<&'a str>,
+        order_dir: Option<&'a str>,
+    ) -> impl Stream<Item = Result<User, TautulliError>> + 'a {
+        paginate(PAGE_SIZE, move |start| async move {
+            Ok(self
+                .get_users_table(order_column, order_dir, start, PAGE_SIZE)
+                .await?
+                .data)
+        })
     }
 
-    #[instrument(skip(self), ret, level = "debug")]
+    /// A page of the user's history, most recent first, `user_history` streams all of it.
     pub async fn get_user_history(
         &self,
         user_id: &str,
         media_type: MediaType,
-        start_date: &chrono::NaiveDate,
-    ) -> Result<GetHistory> {
-        let user_id = user_id.to_string();
-        let params = vec![
-            ("apikey", self.api_key.clone()),
-            ("cmd", "get_history".into()),
-            ("user_id", user_id),
+        start_date: Option<&chrono::NaiveDate>,
+        start: usize,
+        length: usize,
+    ) -> Result<GetHistory, TautulliError> {
+        self.user_history_page(user_id, media_type, start_date, start, length, true)
+            .await
+    }
+
+    #[instrument(skip(self), ret, level = "debug")]
+    async fn user_history_page(
+        &self,
+        user_id: &str,
+        media_type: MediaType,
+        start_date: Option<&chrono::NaiveDate>,
+        start: usize,
+        length: usize,
+        cached: bool,
+    ) -> Result<GetHistory, TautulliError> {
+        let mut params = vec![
+            ("user_id", user_id.to_string()),
             ("media_type", media_type.to_string()),
-            ("after", start_date.format("%Y-%m-%d").to_string()),
+            ("start", start.to_string()),
+            ("length", length.to_string()),
         ];
+        if let Some(start_date) = start_date {
+            params.push(("after", start_date.format("%Y-%m-%d").to_string()));
+        }
+        match cached {
+            true => self.request("get_history", params).await,
+            false => self.request_uncached("get_history", params).await,
+        }
+    }
 
-        let url = Url::parse_with_params(&format!("{}/api/v2", self.url), &params)?;
-        let response: ApiResponse<GetHistory> = self.client.get(url).send().await?.json().await?;
-
-        Ok(response.response.data)
+    /// Every item in the user's history since `start_date`, fetched a page at a time. The pages
+    /// aren't cached, as each is only read once.
+    pub fn user_history<'a>(
+        &'a self,
+        user_id: &'a str,
+        media_type: MediaType,
+        start_date: Option<&'a chrono::NaiveDate>,
+    ) -> impl Stream<Item = Result<HistoryItem, TautulliError>> + 'a {
+        paginate(PAGE_SIZE, move |start| async move {
+            Ok(self
+                .user_history_page(user_id, media_type, start_date, start, PAGE_SIZE, false)
+                .await?
+                .data)
+        })
     }
 }
@@ -30,6 +30,7 @@ use crate::{
         },
         AppServices,
     },
+    tasks::JobReport,
 };
 
 #[derive(Clone, Debug)]
@@ -68,7 +69,11 @@ struct LibraryStatCategoryChannels {
     tv_episodes: Option<ChannelData>,
 }
 
-pub async fn run(config: &AppConfig, services: &AppServices) -> Result<()> {
+pub async fn run(
+    config: &AppConfig,
+    services: &AppServices,
+    _report: &mut JobReport,
+) -> Result<()> {
     let client = &services.discord_service;
 
     let roles = client
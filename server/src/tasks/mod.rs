@@ -1,5 +1,149 @@
+use std::{
+    fmt::Display,
+    panic::AssertUnwindSafe,
+    time::Instant,
+};
+
+use anyhow::Result;
+use async_graphql::futures_util::FutureExt;
+use derive_more::Display;
+use tracing::Instrument;
+
+use crate::{
+    config::AppConfig,
+    entities::job_run::{
+        JobRunItemError,
+        JobRunStatus,
+    },
+    services::{
+        events::Event,
+        AppServices,
+    },
+    telemetry,
+};
+
 pub mod channel_refresh;
 pub mod metadata;
 pub mod requests_upgrade;
+pub mod role_sync;
 pub mod token_maintenance;
 pub mod user_refresh;
+
+/// Tasks which can be run on a schedule by the daemon, or once from their own subcommand.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq)]
+pub enum Job {
+    #[display(fmt = "channel-refresh")]
+    ChannelRefresh,
+    #[display(fmt = "requests-upgrade")]
+    RequestsUpgrade,
+    #[display(fmt = "role-sync")]
+    RoleSync,
+    #[display(fmt = "token-maintenance")]
+    TokenMaintenance,
+    #[display(fmt = "user-refresh")]
+    UserRefresh,
+}
+
+/// What a single task run did, recorded in the `job_run` table once the run finishes.
+#[derive(Debug, Default)]
+pub struct JobReport {
+    pub items_processed: i32,
+    pub item_errors: Vec<JobRunItemError>,
+}
+
+impl JobReport {
+    pub fn succeeded(&mut self) {
+        self.items_processed += 1;
+    }
+
+    pub fn failed(&mut self, item: impl Display, error: impl Display) {
+        self.items_processed += 1;
+        self.item_errors.push(JobRunItemError {
+            item: item.to_string(),
+            error: error.to_string(),
+        });
+    }
+}
+
+impl Job {
+    /// Whether an event should run the task straight away, rather than waiting for its schedule.
+    pub fn triggered_by(&self, event: &Event) -> bool {
+        matches!((self, event), (Job::ChannelRefresh, Event::Tautulli(_)))
+    }
+
+    /// Run the task, recording its outcome in the job run history.
+    pub async fn run(&self, config: &AppConfig, services: &AppServices) -> Result<JobReport> {
+        // Each run is its own trace, rather than part of the scheduler's.
+        let span = tracing::info_span!(parent: None, "job", job = %self);
+        self.run_job(config, services).instrument(span).await
+    }
+
+    async fn run_job(&self, config: &AppConfig, services: &AppServices) -> Result<JobReport> {
+        let job_run = match services.job_runs_service.start(&self.to_string()).await {
+            Ok(job_run) => Some(job_run),
+            Err(err) => {
+                tracing::warn!("unable to record start of {self}: {:?}", err);
+                None
+            }
+        };
+
+        let start = Instant::now();
+        let mut report = JobReport::default();
+        let task = async {
+            match self {
+                Job::ChannelRefresh => channel_refresh::run(config, services, &mut report).await,
+                Job::RequestsUpgrade => requests_upgrade::run(services, &mut report).await,
+                Job::RoleSync => role_sync::run(config, services, &mut report).await,
+                Job::TokenMaintenance => {
+                    token_maintenance::run(config, services, &mut report).await
+                }
+                Job::UserRefresh => user_refresh::run(config, services, &mut report).await,
+            }
+        };
+        // A panic is recorded as a failure, rather than leaving the run as `Running`.
+        let result = AssertUnwindSafe(task)
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(anyhow::anyhow!("{self} panicked: {message}"))
+            });
+
+        let status = match &result {
+            Ok(_) if report.item_errors.is_empty() => JobRunStatus::Succeeded,
+            Ok(_) => JobRunStatus::CompletedWithErrors,
+            Err(_) => JobRunStatus::Failed,
+        };
+        telemetry::record_job(
+            self.to_string(),
+            match status {
+                JobRunStatus::Succeeded => "succeeded",
+                JobRunStatus::CompletedWithErrors => "completed_with_errors",
+                _ => "failed",
+            },
+            start.elapsed(),
+        );
+
+        if let Some(job_run) = job_run {
+            let error = result.as_ref().err().map(|err| format!("{err:#}"));
+            if let Err(err) = services
+                .job_runs_service
+                .finish(
+                    job_run.id,
+                    status,
+                    report.items_processed,
+                    error,
+                    report.item_errors.clone(),
+                )
+                .await
+            {
+                tracing::warn!("unable to record result of {self}: {:?}", err);
+            }
+        }
+        result.map(|_| report)
+    }
+}
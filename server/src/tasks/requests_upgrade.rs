@@ -1,11 +1,209 @@
+use std::pin::pin;
+
 use anyhow::Result;
+use async_graphql::futures_util::TryStreamExt;
+use clap::ValueEnum;
+use serde::Serialize;
 
-use crate::services::AppServices;
+use crate::{
+    config::RequestsUpgradeConfig,
+    entities::job_run::JobRunItemError,
+    services::{
+        overseerr::models::UserRequestSettings,
+        AppServices,
+    },
+    tasks::JobReport,
+};
 
-pub async fn run(services: &AppServices) -> Result<()> {
-    let overseerr_users = services.overseerr_service.get_users().await?;
-    for user in overseerr_users {
-        services.overseerr_service.set_request_tier(&user).await?;
+pub async fn run(services: &AppServices, report: &mut JobReport) -> Result<()> {
+    let overseerr_users = services.overseerr_service.users();
+    let mut overseerr_users = pin!(overseerr_users);
+    while let Some(user) = overseerr_users.try_next().await? {
+        match services.overseerr_service.set_request_tier(&user).await {
+            Ok(_) => report.succeeded(),
+            Err(err) => {
+                tracing::error!(
+                    "failed to set request tier for {}: {err:?}",
+                    user.display_name
+                );
+                report.failed(&user.display_name, err);
+            }
+        }
     }
     Ok(())
 }
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// A user whose request quotas would change.
+#[derive(Debug, Serialize)]
+pub struct TierChange {
+    pub display_name: String,
+    pub plex_username: String,
+    pub watch_hours: i64,
+    pub current_tier: String,
+    pub target_tier: String,
+    pub current: UserRequestSettings,
+    pub target: UserRequestSettings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub users: usize,
+    pub changes: Vec<TierChange>,
+    /// Users whose tier couldn't be worked out, e.g. as Tautulli has no stats for them.
+    pub errors: Vec<JobRunItemError>,
+}
+
+const DEFAULT_TIER: &str = "Default";
+const CUSTOM_TIER: &str = "Custom";
+
+/// Name the tier matching a user's current quotas, as Overseerr doesn't store it.
+fn tier_name(config: &RequestsUpgradeConfig, settings: &UserRequestSettings) -> String {
+    if *settings == UserRequestSettings::default() {
+        return String::from(DEFAULT_TIER);
+    }
+    config
+        .tiers
+        .iter()
+        .find(|&tier| UserRequestSettings::from(tier) == *settings)
+        .map(|tier| tier.name.clone())
+        .unwrap_or_else(|| String::from(CUSTOM_TIER))
+}
+
+/// Work out which users would move tier, without changing anything.
+pub async fn dry_run(
+    config: &RequestsUpgradeConfig,
+    services: &AppServices,
+) -> Result<DryRunReport> {
+    let overseerr_svc = &services.overseerr_service;
+    let mut report = DryRunReport {
+        users: 0,
+        changes: vec![],
+        errors: vec![],
+    };
+    let overseerr_users = overseerr_svc.users();
+    let mut overseerr_users = pin!(overseerr_users);
+    while let Some(user) = overseerr_users.try_next().await? {
+        report.users += 1;
+        let user_id = user.id.to_string();
+        let result = tokio::try_join!(
+            overseerr_svc.get_user_request_settings(&user_id),
+            overseerr_svc.target_request_tier(&user),
+        );
+        let (current, (standing, tier)) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                report.errors.push(JobRunItemError {
+                    item: user.display_name,
+                    error: format!("{err:#}"),
+                });
+                continue;
+            }
+        };
+        let target = tier
+            .as_ref()
+            .map(UserRequestSettings::from)
+            .unwrap_or_default();
+        if current == target {
+            continue;
+        }
+        report.changes.push(TierChange {
+            current_tier: tier_name(config, &current),
+            target_tier: tier
+                .map(|tier| tier.name)
+                .unwrap_or_else(|| String::from(DEFAULT_TIER)),
+            display_name: user.display_name,
+            plex_username: user.plex_username,
+            watch_hours: standing.total_watch_hours(),
+            current,
+            target,
+        });
+    }
+    Ok(report)
+}
+
+impl DryRunReport {
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        Ok(match format {
+            OutputFormat::Json => serde_json::to_string_pretty(self)?,
+            OutputFormat::Text => {
+                let header = ["User", "Watch hours", "Current tier", "Target tier"];
+                let rows: Vec<[String; 4]> = self
+                    .changes
+                    .iter()
+                    .map(|change| {
+                        [
+                            change.display_name.clone(),
+                            change.watch_hours.to_string(),
+                            change.current_tier.clone(),
+                            change.target_tier.clone(),
+                        ]
+                    })
+                    .collect();
+                let widths: Vec<usize> = (0..header.len())
+                    .map(|i| {
+                        rows.iter()
+                            .map(|row| row[i].chars().count())
+                            .chain([header[i].len()])
+                            .max()
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                let format_row = |row: &[&str]| {
+                    row.iter()
+                        .zip(&widths)
+                        .map(|(cell, width)| format!("{cell:<width$}"))
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                        .trim_end()
+                        .to_owned()
+                };
+
+                let mut lines = vec![format_row(&header)];
+                lines.extend(
+                    rows.iter()
+                        .map(|row| format_row(&row.each_ref().map(String::as_str))),
+                );
+                lines.push(format!(
+                    "{} of {} users would change tier",
+                    self.changes.len(),
+                    self.users
+                ));
+                lines.extend(
+                    self.errors.iter().map(|err| {
+                        format!("unable to work out tier for {}: {}", err.item, err.error)
+                    }),
+                );
+                lines.join("\n")
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tier_name_works() {
+        let config = RequestsUpgradeConfig::default();
+        let bronze = UserRequestSettings::from(&config.tiers[0]);
+        assert_eq!(tier_name(&config, &bronze), "Bronze");
+        assert_eq!(
+            tier_name(&config, &UserRequestSettings::default()),
+            DEFAULT_TIER
+        );
+
+        let custom = UserRequestSettings {
+            movie_quota_limit: Some(1),
+            ..bronze
+        };
+        assert_eq!(tier_name(&config, &custom), CUSTOM_TIER);
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+    },
+    fmt,
+};
+
+use anyhow::Result;
+use serenity::model::prelude::Role;
+
+use crate::{
+    config::{
+        AppConfig,
+        RequestLimitTier,
+    },
+    entities::{
+        job_run::JobRunItemError,
+        plex_user,
+    },
+    services::AppServices,
+    tasks::JobReport,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoleEdit {
+    Add,
+    Remove,
+}
+
+/// A role to give to or take away from a member.
+#[derive(Debug)]
+pub struct RoleChange {
+    pub discord_user_id: u64,
+    pub username: String,
+    pub edit: RoleEdit,
+    pub role_id: u64,
+    pub role: String,
+    /// Why the member should or shouldn't have the role, written to the audit log.
+    pub reason: String,
+}
+
+impl fmt::Display for RoleChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.edit {
+            RoleEdit::Add => write!(f, "add {} to {}", self.role, self.username),
+            RoleEdit::Remove => write!(f, "remove {} from {}", self.role, self.username),
+        }?;
+        write!(f, " ({})", self.reason)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RoleSyncPlan {
+    pub changes: Vec<RoleChange>,
+    /// Members whose request tier couldn't be looked up.
+    pub errors: Vec<JobRunItemError>,
+}
+
+/// The configured roles, resolved to their ids in the guild.
+#[derive(Debug, Default)]
+struct ManagedRoles {
+    subscriber: Option<u64>,
+    tiers: HashMap<String, u64>,
+    names: HashMap<u64, String>,
+}
+
+impl ManagedRoles {
+    fn resolve(config: &AppConfig, guild_roles: &[Role]) -> Result<Self> {
+        let resolve = |role: &str| {
+            guild_roles
+                .iter()
+                .find(|guild_role| guild_role.id.to_string() == role || guild_role.name == role)
+                .map(|guild_role| guild_role.id.get())
+                .ok_or_else(|| anyhow::anyhow!("role {role:?} not found in the Discord server"))
+        };
+        let role_sync = &config.role_sync;
+        let subscriber = role_sync
+            .subscriber_role
+            .as_deref()
+            .map(resolve)
+            .transpose()?;
+        let tiers = role_sync
+            .tier_roles
+            .iter()
+            .map(|(tier, role)| Ok((tier.clone(), resolve(role)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let names = guild_roles
+            .iter()
+            .map(|role| (role.id.get(), role.name.clone()))
+            .collect();
+        Ok(Self {
+            subscriber,
+            tiers,
+            names,
+        })
+    }
+
+    /// The roles to give to or take away from a member, with the reason for each.
+    fn member_changes(
+        &self,
+        member_role_ids: &[u64],
+        is_subscriber: bool,
+        tier: Option<&str>,
+    ) -> Vec<(RoleEdit, u64, String)> {
+        let mut wanted = BTreeMap::new();
+        if let (true, Some(role_id)) = (is_subscriber, self.subscriber) {
+            wanted.insert(role_id, String::from("has access to the Plex server"));
+        }
+        if let Some(tier) = tier {
+            if let Some(&role_id) = self.tiers.get(tier) {
+                wanted
+                    .entry(role_id)
+                    .or_insert_with(|| format!("on the {tier} request tier"));
+            }
+        }
+
+        let managed: BTreeSet<u64> = self
+            .subscriber
+            .into_iter()
+            .chain(self.tiers.values().copied())
+            .collect();
+        managed
+            .into_iter()
+            .filter_map(|role_id| {
+                match (member_role_ids.contains(&role_id), wanted.get(&role_id)) {
+                    (false, Some(reason)) => Some((RoleEdit::Add, role_id, reason.clone())),
+                    (true, None) => Some((RoleEdit::Remove, role_id, self.removal_reason(role_id))),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn removal_reason(&self, role_id: u64) -> String {
+        if self.subscriber == Some(role_id) {
+            return String::from("no longer has access to the Plex server");
+        }
+        match self.tiers.iter().find(|(_, &id)| id == role_id) {
+            Some((tier, _)) => format!("no longer on the {tier} request tier"),
+            None => String::from("no longer eligible"),
+        }
+    }
+}
+
+/// Work out which roles need to change, without changing anything.
+pub async fn plan(config: &AppConfig, services: &AppServices) -> Result<RoleSyncPlan> {
+    let mut plan = RoleSyncPlan::default();
+    if config.role_sync.subscriber_role.is_none() && config.role_sync.tier_roles.is_empty() {
+        tracing::info!("no roles configured to sync");
+        return Ok(plan);
+    }
+
+    let guild_id = config.discord.server_id;
+    let discord_svc = &services.discord_service;
+    let roles = ManagedRoles::resolve(config, &discord_svc.get_guild_roles(guild_id).await?)?;
+
+    // A Discord user can have more than one Plex account linked.
+    let mut users: BTreeMap<u64, (String, Vec<plex_user::Model>)> = BTreeMap::new();
+    for (discord_user, plex_user) in services
+        .discord_users_service
+        .list_subscribers()
+        .await
+        .map_err(|err| anyhow::anyhow!(err.message))?
+    {
+        let Ok(discord_user_id) = discord_user.id.parse() else {
+            continue;
+        };
+        let (_, plex_users) = users
+            .entry(discord_user_id)
+            .or_insert_with(|| (discord_user.username, vec![]));
+        plex_users.extend(plex_user);
+    }
+
+    // Every member is checked, so managed roles are also taken away from members who have never
+    // linked their account or whose records have been deleted.
+    for member in discord_svc.list_guild_members(guild_id).await? {
+        if member.user.bot {
+            continue;
+        }
+        let discord_user_id = member.user.id.get();
+        let (username, plex_users) = users
+            .remove(&discord_user_id)
+            .unwrap_or_else(|| (member.user.name.clone(), vec![]));
+        let member_role_ids: Vec<u64> = member.roles.iter().map(|role_id| role_id.get()).collect();
+        match member_changes(config, services, &roles, &member_role_ids, &plex_users).await {
+            Ok(changes) => plan.changes.extend(changes.into_iter().map(
+                |(edit, role_id, reason)| RoleChange {
+                    discord_user_id,
+                    username: username.clone(),
+                    edit,
+                    role_id,
+                    role: roles.names.get(&role_id).cloned().unwrap_or_default(),
+                    reason,
+                },
+            )),
+            Err(err) => plan.errors.push(JobRunItemError {
+                item: username,
+                error: format!("{err:#}"),
+            }),
+        }
+    }
+    for (username, _) in users.values() {
+        tracing::debug!("{username} isn't a member of the Discord server");
+    }
+    Ok(plan)
+}
+
+async fn member_changes(
+    config: &AppConfig,
+    services: &AppServices,
+    roles: &ManagedRoles,
+    member_role_ids: &[u64],
+    plex_users: &[plex_user::Model],
+) -> Result<Vec<(RoleEdit, u64, String)>> {
+    let is_subscriber = plex_users.iter().any(|plex_user| plex_user.is_subscriber);
+    let mut assigned = vec![];
+    for plex_user in plex_users {
+        // The tier last set by `requests-upgrade`.
+        let assignment = services
+            .request_tier_assignments_service
+            .latest(&plex_user.id)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.message))?;
+        assigned.extend(assignment.and_then(|assignment| assignment.tier));
+    }
+    let tier = highest_tier(&config.requests_config.tiers, assigned);
+    Ok(roles.member_changes(member_role_ids, is_subscriber, tier.as_deref()))
+}
+
+/// The highest of the assigned tiers by their order in `requests_config.tiers`, for members with
+/// more than one Plex account linked.
+fn highest_tier(tiers: &[RequestLimitTier], assigned: Vec<String>) -> Option<String> {
+    assigned
+        .into_iter()
+        .max_by_key(|name| tiers.iter().position(|tier| tier.name == *name))
+}
+
+pub async fn run(config: &AppConfig, services: &AppServices, report: &mut JobReport) -> Result<()> {
+    let plan = plan(config, services).await?;
+    for change in &plan.changes {
+        tracing::info!("planned role change: {change}");
+    }
+    tracing::info!(
+        "{} role changes planned, {} members couldn't be checked",
+        plan.changes.len(),
+        plan.errors.len()
+    );
+
+    for err in plan.errors {
+        report.failed(err.item, err.error);
+    }
+    let guild_id = config.discord.server_id;
+    let discord_svc = &services.discord_service;
+    for (i, change) in plan.changes.iter().enumerate() {
+        // Serenity waits out Discord's rate limits, this spreads the edits out further.
+        if i > 0 {
+            tokio::time::sleep(config.role_sync.edit_interval).await;
+        }
+        let reason = format!("role-sync: {}", change.reason);
+        let result = match change.edit {
+            RoleEdit::Add => {
+                discord_svc
+                    .add_member_role(guild_id, change.discord_user_id, change.role_id, &reason)
+                    .await
+            }
+            RoleEdit::Remove => {
+                discord_svc
+                    .remove_member_role(guild_id, change.discord_user_id, change.role_id, &reason)
+                    .await
+            }
+        };
+        match result {
+            Ok(_) => report.succeeded(),
+            Err(err) => {
+                tracing::error!("failed to {change}: {err:?}");
+                report.failed(change, err);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn member_changes_works() {
+        let roles = ManagedRoles {
+            subscriber: Some(1),
+            tiers: HashMap::from([(String::from("Bronze"), 2), (String::from("Gold"), 3)]),
+            names: HashMap::new(),
+        };
+
+        assert_eq!(
+            roles.member_changes(&[], true, Some("Gold")),
+            vec![
+                (
+                    RoleEdit::Add,
+                    1,
+                    String::from("has access to the Plex server")
+                ),
+                (RoleEdit::Add, 3, String::from("on the Gold request tier")),
+            ]
+        );
+        assert_eq!(
+            roles.member_changes(&[1, 2, 4], true, Some("Gold")),
+            vec![
+                (
+                    RoleEdit::Remove,
+                    2,
+                    String::from("no longer on the Bronze request tier")
+                ),
+                (RoleEdit::Add, 3, String::from("on the Gold request tier")),
+            ]
+        );
+        assert_eq!(
+            roles.member_changes(&[1, 3], false, None),
+            vec![
+                (
+                    RoleEdit::Remove,
+                    1,
+                    String::from("no longer has access to the Plex server")
+                ),
+                (
+                    RoleEdit::Remove,
+                    3,
+                    String::from("no longer on the Gold request tier")
+                ),
+            ]
+        );
+        assert!(roles.member_changes(&[1, 3], true, Some("Gold")).is_empty());
+    }
+
+    #[test]
+    fn highest_tier_works() {
+        let tiers: Vec<RequestLimitTier> = ["Bronze", "Silver", "Gold"]
+            .into_iter()
+            .map(|name| RequestLimitTier {
+                name: String::from(name),
+                ..Default::default()
+            })
+            .collect();
+        let assigned = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+
+        assert_eq!(
+            highest_tier(&tiers, assigned(&["Silver", "Gold", "Bronze"])),
+            Some(String::from("Gold"))
+        );
+        assert_eq!(
+            highest_tier(&tiers, assigned(&["Removed", "Bronze"])),
+            Some(String::from("Bronze"))
+        );
+        assert_eq!(highest_tier(&tiers, vec![]), None);
+    }
+}
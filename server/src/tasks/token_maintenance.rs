@@ -11,9 +11,10 @@ use crate::{
     config::AppConfig,
     entities::discord_token,
     services::AppServices,
+    tasks::JobReport,
 };
 
-pub async fn run(config: &AppConfig, services: &AppServices) -> Result<()> {
+pub async fn run(config: &AppConfig, services: &AppServices, report: &mut JobReport) -> Result<()> {
     let tokens = services
         .discord_tokens_service
         .list(None, None, Some(discord_token::TokenStatus::Active))
@@ -29,6 +30,7 @@ pub async fn run(config: &AppConfig, services: &AppServices) -> Result<()> {
                 .set_status(&token.access_token, discord_token::TokenStatus::Expired)
                 .await
                 .map_err(|err| anyhow!(err.message))?;
+            report.succeeded();
         } else if token.expires_at < now + config.token_maintenance.refresh_days_to_expire {
             tracing::info!(
                 "user {:?} token expires at {:?}, refreshing token...",
@@ -43,11 +45,22 @@ pub async fn run(config: &AppConfig, services: &AppServices) -> Result<()> {
                         .set_status(&token.access_token, discord_token::TokenStatus::Renewed)
                         .await
                         .map_err(|err| anyhow!(err.message))?;
+                    report.succeeded();
+                }
+                Err(err) => {
+                    tracing::error!("error: {:?}", err);
+                    report.failed(&token.discord_user_id, err);
                 }
-                Err(err) => tracing::error!("error: {:?}", err),
             };
         }
     }
+
+    let expired_sessions = services
+        .sessions_service
+        .delete_expired()
+        .await
+        .map_err(|err| anyhow!(err.message))?;
+    tracing::info!("deleted {expired_sessions} expired sessions");
     Ok(())
 }
 
@@ -14,9 +14,10 @@ use crate::{
         tautulli::models::QueryDays,
         AppServices,
     },
+    tasks::JobReport,
 };
 
-pub async fn run(config: &AppConfig, services: &AppServices) -> Result<()> {
+pub async fn run(config: &AppConfig, services: &AppServices, report: &mut JobReport) -> Result<()> {
     let users = services
         .discord_users_service
         .list_users_for_refresh()
@@ -24,13 +25,20 @@ pub async fn run(config: &AppConfig, services: &AppServices) -> Result<()> {
         .unwrap();
     tracing::info!("Refreshing {} users", users.len());
     for (discord_user, plex_user) in users {
-        if plex_user.is_none() {
-            anyhow::bail!("No plex user found! {:?}", discord_user);
-        }
-        let plex_user = plex_user.unwrap();
+        let Some(plex_user) = plex_user else {
+            tracing::error!("No plex user found! {:?}", discord_user);
+            report.failed(&discord_user.username, "no plex user found");
+            continue;
+        };
         match refresh_user_stats(config, services, &discord_user, &plex_user).await {
-            Ok(_) => tracing::info!("successfully refreshed {}", discord_user.username),
-            Err(err) => tracing::error!("failed to refresh {}: {err:?}", discord_user.username),
+            Ok(_) => {
+                tracing::info!("successfully refreshed {}", discord_user.username);
+                report.succeeded();
+            }
+            Err(err) => {
+                tracing::error!("failed to refresh {}: {err:?}", discord_user.username);
+                report.failed(&discord_user.username, err);
+            }
         };
     }
     Ok(())
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::OnceLock,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use anyhow::Result;
+use axum::{
+    extract::{
+        MatchedPath,
+        Request,
+    },
+    middleware::Next,
+    response::Response,
+};
+use metrics::{
+    counter,
+    gauge,
+    histogram,
+};
+use metrics_exporter_prometheus::{
+    Matcher,
+    PrometheusBuilder,
+    PrometheusHandle,
+};
+use opentelemetry::{
+    global,
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace,
+    Resource,
+};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter,
+};
+
+use crate::{
+    config::TracingConfig,
+    entities::discord_token::TokenStatus,
+    services::AppServices,
+};
+
+/// Latency buckets in seconds, for both HTTP requests and upstream calls.
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Set up logging, along with exporting traces when enabled.
+pub fn init_tracing(config: &TracingConfig) -> Result<()> {
+    let otel = if config.enabled {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer =
+            opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .http()
+                        .with_endpoint(&config.endpoint),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", config.service_name.clone()),
+                ])))
+                .install_batch(runtime::Tokio)?;
+        Some(tracing_opentelemetry::layer().with_tracer(tracer))
+    } else {
+        None
+    };
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel)
+        .try_init()?;
+    Ok(())
+}
+
+/// Flush any spans which haven't been exported yet.
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
+
+/// Propagate the current span to the services we call, so their traces join ours.
+pub trait InjectTraceContext {
+    fn inject_trace_context(self) -> Self;
+}
+
+impl InjectTraceContext for reqwest::RequestBuilder {
+    fn inject_trace_context(self) -> Self {
+        let context = tracing::Span::current().context();
+        let mut headers = HashMap::new();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut headers)
+        });
+        headers
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .fold(self, |builder, (name, value)| builder.header(name, value))
+    }
+}
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the Prometheus recorder, metrics recorded before this are dropped.
+pub fn prometheus() -> &'static PrometheusHandle {
+    PROMETHEUS.get_or_init(|| {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Suffix(String::from("duration_seconds")),
+                DURATION_BUCKETS,
+            )
+            .expect("buckets are not empty")
+            .install_recorder()
+            .expect("failed to install Prometheus recorder")
+    })
+}
+
+/// Record the latency and outcome of a call to Tautulli, Overseerr, Plex or Discord.
+pub async fn observe<T, E>(
+    service: &'static str,
+    method: &'static str,
+    call: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let start = Instant::now();
+    let result = call.await;
+    histogram!(
+        "displex_upstream_request_duration_seconds",
+        "service" => service,
+        "method" => method,
+    )
+    .record(start.elapsed().as_secs_f64());
+    if result.is_err() {
+        counter!(
+            "displex_upstream_request_errors_total",
+            "service" => service,
+            "method" => method,
+        )
+        .increment(1);
+    }
+    result
+}
+
+/// Record the latency and outcome of a task run.
+pub fn record_job(job: String, status: &'static str, elapsed: Duration) {
+    histogram!("displex_job_duration_seconds", "job" => job.clone()).record(elapsed.as_secs_f64());
+    counter!("displex_job_runs_total", "job" => job, "status" => status).increment(1);
+}
+
+/// Axum middleware recording the latency and status of every request.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    // The route rather than the path, so ids in paths don't create new series.
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| String::from("unmatched"));
+    let method = request.method().to_string();
+
+    let response = next.run(request).await;
+    let status = response.status().as_u16().to_string();
+    histogram!(
+        "displex_http_request_duration_seconds",
+        "method" => method.clone(),
+        "path" => path.clone(),
+    )
+    .record(start.elapsed().as_secs_f64());
+    counter!(
+        "displex_http_requests_total",
+        "method" => method,
+        "path" => path,
+        "status" => status,
+    )
+    .increment(1);
+    response
+}
+
+/// Update the gauges which are read from Tautulli and the database, called on every scrape.
+///
+/// Each group of gauges is updated on its own, so e.g. Tautulli being unavailable doesn't stop the
+/// user and token gauges from updating.
+pub async fn update_gauges(services: &AppServices) {
+    let (activity, users, tokens) = tokio::join!(
+        update_activity_gauges(services),
+        update_user_gauges(services),
+        update_token_gauges(services),
+    );
+    for (gauges, result) in [("activity", activity), ("user", users), ("token", tokens)] {
+        if let Err(err) = result {
+            tracing::warn!("unable to update {gauges} gauges: {err:?}");
+        }
+    }
+}
+
+async fn update_activity_gauges(services: &AppServices) -> Result<()> {
+    let activity = services.tautulli_service.get_activity().await?;
+    gauge!("displex_streams").set(activity.stream_count.parse::<f64>().unwrap_or_default());
+    gauge!("displex_streams_direct_play").set(activity.stream_count_direct_play as f64);
+    gauge!("displex_streams_direct_stream").set(activity.stream_count_direct_stream as f64);
+    gauge!("displex_streams_transcode").set(activity.stream_count_transcode as f64);
+    gauge!("displex_bandwidth_kbps", "location" => "total").set(activity.total_bandwidth as f64);
+    gauge!("displex_bandwidth_kbps", "location" => "lan").set(activity.lan_bandwidth as f64);
+    gauge!("displex_bandwidth_kbps", "location" => "wan").set(activity.wan_bandwidth as f64);
+    Ok(())
+}
+
+async fn update_user_gauges(services: &AppServices) -> Result<()> {
+    let plex_users_svc = &services.plex_users_service;
+    let linked = plex_users_svc
+        .count(None)
+        .await
+        .map_err(|err| anyhow::anyhow!(err.message))?;
+    let subscribed = plex_users_svc
+        .count(Some(true))
+        .await
+        .map_err(|err| anyhow::anyhow!(err.message))?;
+    gauge!("displex_linked_users").set(linked as f64);
+    gauge!("displex_subscribed_users").set(subscribed as f64);
+    Ok(())
+}
+
+async fn update_token_gauges(services: &AppServices) -> Result<()> {
+    for status in [
+        TokenStatus::Active,
+        TokenStatus::Revoked,
+        TokenStatus::Renewed,
+        TokenStatus::Expired,
+    ] {
+        let count = services
+            .discord_tokens_service
+            .count(status)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.message))?;
+        gauge!("displex_discord_tokens", "status" => format!("{status:?}").to_lowercase())
+            .set(count as f64);
+    }
+    Ok(())
+}